@@ -0,0 +1,223 @@
+// Art-Net timecode (OpTimeCode), enabled with the `artnet` feature
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::{SMPTETimecode, Timezone};
+use crate::error::ArtNetError;
+
+pub const ARTNET_PORT: u16 = 6454;
+pub const ART_TIMECODE_LEN: usize = 19;
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const OP_TIMECODE: u16 = 0x9700;
+const PROTOCOL_VERSION: u16 = 14;
+
+pub fn art_timecode(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+    stream_id: u8,
+) -> Result<[u8; ART_TIMECODE_LEN], ArtNetError> {
+    let kind = rate.smpte_type().ok_or(ArtNetError::UnsupportedRate)?;
+    let mut packet = [0u8; ART_TIMECODE_LEN];
+    packet[..8].copy_from_slice(ARTNET_ID);
+    packet[8..10].copy_from_slice(&OP_TIMECODE.to_le_bytes());
+    packet[10..12].copy_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    packet[13] = stream_id;
+    packet[14] = timecode.frame();
+    packet[15] = timecode.seconds();
+    packet[16] = timecode.minutes();
+    packet[17] = timecode.hours();
+    packet[18] = kind;
+    Ok(packet)
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtNetTimecode {
+    pub timecode: SMPTETimecode,
+    pub rate: FrameRate,
+    pub stream_id: u8,
+}
+
+// Fails with `InvalidPacket` for anything that isn't an ArtTimeCode packet
+pub fn parse_art_timecode(packet: &[u8]) -> Result<ArtNetTimecode, ArtNetError> {
+    if packet.len() < ART_TIMECODE_LEN
+        || &packet[..8] != ARTNET_ID
+        || u16::from_le_bytes([packet[8], packet[9]]) != OP_TIMECODE
+    {
+        return Err(ArtNetError::InvalidPacket);
+    }
+    let [frame, seconds, minutes, hours, kind] =
+        [packet[14], packet[15], packet[16], packet[17], packet[18]];
+    if frame > 29 || seconds > 59 || minutes > 59 || hours > 23 || kind > 3 {
+        return Err(ArtNetError::InvalidPacket);
+    }
+    Ok(ArtNetTimecode {
+        timecode: SMPTETimecode::new(Timezone::default(), 0, 0, 0, hours, minutes, seconds, frame),
+        rate: FrameRate::from_smpte_type(kind),
+        stream_id: packet[13],
+    })
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ArtNetSenderConfig {
+    pub bind: SocketAddr,
+    // Broadcast to the whole Art-Net network by default
+    pub target: SocketAddr,
+    pub rate: FrameRate,
+    pub stream_id: u8,
+}
+
+impl Default for ArtNetSenderConfig {
+    fn default() -> Self {
+        ArtNetSenderConfig {
+            bind: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into(),
+            target: SocketAddrV4::new(Ipv4Addr::BROADCAST, ARTNET_PORT).into(),
+            rate: FrameRate::default(),
+            stream_id: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ArtNetSender {
+    config: ArtNetSenderConfig,
+    socket: UdpSocket,
+}
+
+impl ArtNetSender {
+    pub fn try_new(config: &ArtNetSenderConfig) -> Result<Self, ArtNetError> {
+        config
+            .rate
+            .smpte_type()
+            .ok_or(ArtNetError::UnsupportedRate)?;
+        let socket = UdpSocket::bind(config.bind)?;
+        if config.bind.is_ipv4() {
+            socket.set_broadcast(true)?;
+        }
+        Ok(ArtNetSender {
+            config: *config,
+            socket,
+        })
+    }
+
+    pub fn send(&self, timecode: &SMPTETimecode) -> Result<(), ArtNetError> {
+        let packet = art_timecode(timecode, self.config.rate, self.config.stream_id)?;
+        self.socket.send_to(&packet, self.config.target)?;
+        Ok(())
+    }
+
+    // Sends the timecode of a decoded frame, call it for every frame `LTCDecoder::read` returns
+    pub fn send_frame(&self, frame: &LTCFrameExt) -> Result<(), ArtNetError> {
+        self.send(&frame.ltc().to_timecode(LtcBgFlags::default()))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ArtNetReceiverConfig {
+    pub bind: SocketAddr,
+    // None blocks until a packet arrives
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ArtNetReceiverConfig {
+    fn default() -> Self {
+        ArtNetReceiverConfig {
+            bind: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, ARTNET_PORT).into(),
+            timeout: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ArtNetReceiver {
+    socket: UdpSocket,
+    buf: Vec<u8>,
+}
+
+impl ArtNetReceiver {
+    pub fn try_new(config: &ArtNetReceiverConfig) -> Result<Self, ArtNetError> {
+        let socket = UdpSocket::bind(config.bind)?;
+        socket.set_read_timeout(config.timeout)?;
+        Ok(ArtNetReceiver {
+            socket,
+            buf: vec![0; 1024],
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ArtNetError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    // Waits for the next timecode packet, other Art-Net traffic is skipped. A timeout comes
+    // back as an `Io` error.
+    pub fn recv(&mut self) -> Result<(ArtNetTimecode, SocketAddr), ArtNetError> {
+        loop {
+            let (len, source) = self.socket.recv_from(&mut self.buf)?;
+            if let Ok(timecode) = parse_art_timecode(&self.buf[..len]) {
+                return Ok((timecode, source));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_art_timecode() {
+        let timecode: SMPTETimecode = "10:20:30:28".parse().unwrap();
+        let packet = art_timecode(&timecode, FrameRate::FPS_29_97_DF, 3).unwrap();
+        assert_eq!(
+            packet,
+            [
+                b'A', b'r', b't', b'-', b'N', b'e', b't', 0, 0x00, 0x97, 0, 14, 0, 3, 28, 30, 20,
+                10, 2
+            ]
+        );
+        let parsed = parse_art_timecode(&packet).unwrap();
+        assert_eq!(parsed.timecode.to_time_string(false), "10:20:30:28");
+        assert_eq!(parsed.rate, FrameRate::FPS_29_97_DF);
+        assert_eq!(parsed.stream_id, 3);
+
+        let mut invalid = packet;
+        invalid[8] = 0x00;
+        invalid[9] = 0x50;
+        assert!(parse_art_timecode(&invalid).is_err());
+        assert!(parse_art_timecode(&packet[..18]).is_err());
+        assert!(art_timecode(&timecode, FrameRate::new(50, 1, false), 0).is_err());
+    }
+
+    #[test]
+    fn test_localhost() {
+        let localhost = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0).into();
+        let mut receiver = ArtNetReceiver::try_new(&ArtNetReceiverConfig {
+            bind: localhost,
+            timeout: Some(Duration::from_secs(5)),
+        })
+        .unwrap();
+        let sender = ArtNetSender::try_new(&ArtNetSenderConfig {
+            bind: localhost,
+            target: receiver.local_addr().unwrap(),
+            rate: FrameRate::FPS_25,
+            stream_id: 0,
+        })
+        .unwrap();
+
+        // Other Art-Net packets are skipped
+        sender
+            .socket
+            .send_to(b"Art-Net\0\x00\x20\x00\x0e", receiver.local_addr().unwrap())
+            .unwrap();
+        for frames in [0, 1, 24 * 60 * 60 * 25 - 1] {
+            let timecode = SMPTETimecode::from_frames(frames, FrameRate::FPS_25);
+            sender.send(&timecode).unwrap();
+            let (received, source) = receiver.recv().unwrap();
+            assert_eq!(source, sender.socket.local_addr().unwrap());
+            assert_eq!(received.rate, FrameRate::FPS_25);
+            assert_eq!(received.timecode.to_frames(FrameRate::FPS_25), frames);
+        }
+    }
+}
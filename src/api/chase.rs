@@ -0,0 +1,380 @@
+use std::f64::consts::PI;
+
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::stamp::{frame_boundary, unwrap_frames};
+use super::SMPTETimecode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LockState {
+    Unlocked,
+    Locking,
+    Locked,
+    Freewheel,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LockTransition {
+    pub from: LockState,
+    pub to: LockState,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ChaserConfig {
+    pub sample_rate: f64,
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // Samples between a frame ending in the stream and the decoder reporting its end
+    pub latency: f64,
+    // Consecutive frames needed before the chaser considers itself locked
+    pub lock_frames: u32,
+    // Frames the clock keeps running without LTC before it gives up
+    pub freewheel_frames: u32,
+    // Bandwidth of the delay locked loop in Hz, lower is smoother but slower to follow
+    pub bandwidth: f64,
+}
+
+impl Default for ChaserConfig {
+    fn default() -> Self {
+        ChaserConfig {
+            sample_rate: 48_000.0,
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            latency: 0.0,
+            lock_frames: 4,
+            freewheel_frames: 25,
+            bandwidth: 1.0,
+        }
+    }
+}
+
+// Follows incoming LTC with a second order delay locked loop on the frame boundaries, see
+// "Using a DLL to filter time" by F. Adriaensen. When frames stop arriving the loop keeps
+// running at the last measured speed for `freewheel_frames`.
+#[derive(Debug)]
+pub struct Chaser {
+    config: ChaserConfig,
+    state: LockState,
+    // Frames since midnight (not wrapped) of the frame that started at `t0`
+    frames: i64,
+    // +1 when playing forwards, -1 in reverse
+    direction: i64,
+    // Filtered boundary of the current frame and predicted boundary of the next one
+    t0: f64,
+    t1: f64,
+    // Filtered frame period in samples
+    period: f64,
+    b: f64,
+    c: f64,
+    // Consistent frames while locking, missed frames while freewheeling
+    count: u32,
+    // Unfiltered frames and boundary of the last decoded frame
+    last: Option<(i64, f64)>,
+}
+
+impl Chaser {
+    pub fn new(config: &ChaserConfig) -> Self {
+        let period = config.rate.samples_per_frame(config.sample_rate);
+        let omega = 2.0 * PI * config.bandwidth * period / config.sample_rate;
+        Chaser {
+            config: *config,
+            state: LockState::Unlocked,
+            frames: 0,
+            direction: 1,
+            t0: 0.0,
+            t1: 0.0,
+            period,
+            b: 2f64.sqrt() * omega,
+            c: omega * omega,
+            count: 0,
+            last: None,
+        }
+    }
+
+    pub fn state(&self) -> LockState {
+        self.state
+    }
+
+    // Playback speed relative to the nominal frame rate, negative in reverse
+    pub fn speed(&self) -> f64 {
+        let nominal = self.config.rate.samples_per_frame(self.config.sample_rate);
+        self.direction as f64 * nominal / self.period
+    }
+
+    // Fractional frames since midnight at a stream position
+    pub fn position_at(&self, sample: f64) -> Option<f64> {
+        match self.state {
+            LockState::Unlocked => None,
+            _ => {
+                Some(self.frames as f64 + self.direction as f64 * (sample - self.t0) / self.period)
+            }
+        }
+    }
+
+    // The timecode running at a stream position and how far into that frame (0..1) it is
+    pub fn timecode_at(&self, sample: f64) -> Option<(SMPTETimecode, f64)> {
+        let position = self.position_at(sample)?;
+        let frames = position.floor();
+        Some((
+            SMPTETimecode::from_frames(frames as i64, self.config.rate),
+            position - frames,
+        ))
+    }
+
+    pub fn reset(&mut self) {
+        *self = Chaser::new(&self.config);
+    }
+
+    pub fn push(&mut self, frame: &LTCFrameExt) -> Option<LockTransition> {
+        let before = self.state;
+        let boundary = frame_boundary(frame, self.config.latency);
+        let direction = if frame.reverse() { -1 } else { 1 };
+        let timecode = frame.ltc().to_timecode(self.config.flags);
+        let frames = unwrap_frames(
+            timecode.to_frames(self.config.rate),
+            self.frames,
+            self.config.rate,
+        );
+
+        // Account for any frames that went missing since the last one
+        self.advance(boundary);
+
+        let consistent = self.state != LockState::Unlocked
+            && direction == self.direction
+            && frames == self.frames + self.direction
+            && (boundary - self.t1).abs() < self.period / 2.0;
+
+        if consistent {
+            self.track(boundary);
+            self.state = match self.state {
+                LockState::Locking if self.count + 1 < self.config.lock_frames => {
+                    self.count += 1;
+                    LockState::Locking
+                }
+                _ => LockState::Locked,
+            };
+        } else {
+            self.restart(frames, direction, boundary);
+        }
+        self.last = Some((frames, boundary));
+
+        transition(before, self.state)
+    }
+
+    // Lets the clock run up to a stream position, call this periodically while no frames
+    // arrive so dropouts are noticed
+    pub fn poll(&mut self, sample: i64) -> Option<LockTransition> {
+        let before = self.state;
+        // A frame is only decoded once all of it has arrived. Forwards that is a period after
+        // its boundary, in reverse bit 0 (the boundary) arrives last.
+        let pending = if self.direction > 0 { self.period } else { 0.0 };
+        self.advance(sample as f64 - pending);
+        transition(before, self.state)
+    }
+
+    // Steps the clock over every frame boundary that should have been seen before `boundary`
+    fn advance(&mut self, boundary: f64) {
+        while self.state != LockState::Unlocked && boundary > self.t1 + self.period / 2.0 {
+            self.t0 = self.t1;
+            self.t1 += self.period;
+            self.frames += self.direction;
+            self.state = match self.state {
+                LockState::Locked if self.config.freewheel_frames > 0 => {
+                    self.count = 1;
+                    LockState::Freewheel
+                }
+                LockState::Freewheel if self.count < self.config.freewheel_frames => {
+                    self.count += 1;
+                    LockState::Freewheel
+                }
+                _ => LockState::Unlocked,
+            };
+        }
+    }
+
+    fn track(&mut self, boundary: f64) {
+        let error = boundary - self.t1;
+        self.t0 = self.t1;
+        self.t1 += self.b * error + self.period;
+        self.period += self.c * error;
+        self.frames += self.direction;
+    }
+
+    fn restart(&mut self, frames: i64, direction: i64, boundary: f64) {
+        // Two frames in a row are enough to start off at the right speed
+        self.period = match self.last {
+            Some((last, previous)) if last + direction == frames && boundary > previous => {
+                boundary - previous
+            }
+            _ => self.config.rate.samples_per_frame(self.config.sample_rate),
+        };
+        self.frames = frames;
+        self.direction = direction;
+        self.t0 = boundary;
+        self.t1 = boundary + self.period;
+        self.count = 1;
+        self.state = if self.config.lock_frames <= 1 {
+            LockState::Locked
+        } else {
+            LockState::Locking
+        };
+    }
+}
+
+fn transition(from: LockState, to: LockState) -> Option<LockTransition> {
+    if from == to {
+        None
+    } else {
+        Some(LockTransition { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::encoder::{LTCEncoder, LTCEncoderConfig};
+    use crate::api::Timezone;
+
+    fn encode(sample_rate: f64, count: usize) -> Vec<u8> {
+        let config = LTCEncoderConfig {
+            sample_rate,
+            ..Default::default()
+        };
+        let mut encoder = LTCEncoder::try_new(&config).unwrap();
+        encoder.set_timecode(&SMPTETimecode::new(
+            Timezone::default(),
+            0,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+        ));
+        let mut audio = Vec::new();
+        for _ in 0..count {
+            encoder.encode_frame();
+            let (buf, len) = encoder.get_buf_ref(true);
+            audio.extend_from_slice(&buf[..len]);
+            encoder.inc_timecode().unwrap();
+        }
+        audio
+    }
+
+    // Feeds the audio in blocks, polling after each one, and returns every transition
+    fn chase(audio: &[u8], chaser: &mut Chaser) -> Vec<LockTransition> {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut transitions = Vec::new();
+        for (i, block) in audio.chunks(480).enumerate() {
+            let position = (i * 480) as i64;
+            decoder.write(block, position);
+            while let Some(frame) = decoder.read() {
+                transitions.extend(chaser.push(&frame));
+            }
+            transitions.extend(chaser.poll(position + block.len() as i64));
+        }
+        transitions
+    }
+
+    #[test]
+    fn test_chaser_locks() {
+        let mut chaser = Chaser::new(&ChaserConfig::default());
+        let audio = encode(48_000.0, 50);
+        let transitions = chase(&audio, &mut chaser);
+
+        assert_eq!(
+            transitions[..2],
+            [
+                LockTransition {
+                    from: LockState::Unlocked,
+                    to: LockState::Locking
+                },
+                LockTransition {
+                    from: LockState::Locking,
+                    to: LockState::Locked
+                }
+            ]
+        );
+        assert_eq!(chaser.state(), LockState::Locked);
+        assert!((chaser.speed() - 1.0).abs() < 0.001);
+
+        // 01:00:00:00 starts at sample 0, the end of the audio is 50 frames later
+        let position = chaser.position_at(audio.len() as f64).unwrap();
+        let expected = FrameRate::FPS_25.hmsf_to_frames(1, 0, 0, 0) as f64 + 50.0;
+        assert!((position - expected).abs() < 0.01, "{position} {expected}");
+    }
+
+    #[test]
+    fn test_chaser_freewheels() {
+        let mut chaser = Chaser::new(&ChaserConfig::default());
+        let mut audio = encode(48_000.0, 60);
+        // Pull the cable for 5 frames
+        audio[30 * 1920..35 * 1920].fill(128);
+        let transitions = chase(&audio, &mut chaser);
+
+        let states: Vec<LockState> = transitions.iter().map(|t| t.to).collect();
+        assert_eq!(
+            states,
+            [
+                LockState::Locking,
+                LockState::Locked,
+                LockState::Freewheel,
+                LockState::Locked
+            ]
+        );
+        let position = chaser.position_at(audio.len() as f64).unwrap();
+        let expected = FrameRate::FPS_25.hmsf_to_frames(1, 0, 0, 0) as f64 + 60.0;
+        assert!((position - expected).abs() < 0.01, "{position} {expected}");
+    }
+
+    #[test]
+    fn test_chaser_unlocks() {
+        let config = ChaserConfig {
+            freewheel_frames: 3,
+            ..Default::default()
+        };
+        let mut chaser = Chaser::new(&config);
+        let mut audio = encode(48_000.0, 30);
+        audio.extend(std::iter::repeat_n(128, 10 * 1920));
+        let transitions = chase(&audio, &mut chaser);
+
+        assert_eq!(
+            transitions.last(),
+            Some(&LockTransition {
+                from: LockState::Freewheel,
+                to: LockState::Unlocked
+            })
+        );
+        assert_eq!(chaser.position_at(0.0), None);
+
+        // Without freewheeling the first missing frame unlocks
+        let config = ChaserConfig {
+            freewheel_frames: 0,
+            ..Default::default()
+        };
+        let mut chaser = Chaser::new(&config);
+        let transitions = chase(&audio, &mut chaser);
+        let states: Vec<LockState> = transitions.iter().map(|t| t.to).collect();
+        assert_eq!(
+            states,
+            [LockState::Locking, LockState::Locked, LockState::Unlocked]
+        );
+    }
+
+    #[test]
+    fn test_chaser_speed() {
+        // Frames that are 10% shorter than nominal play 10% fast
+        let mut chaser = Chaser::new(&ChaserConfig::default());
+        chase(&encode(48_000.0 / 1.1, 100), &mut chaser);
+        assert_eq!(chaser.state(), LockState::Locked);
+        assert!((chaser.speed() - 1.1).abs() < 0.01, "{}", chaser.speed());
+
+        // Far off speeds lock as well, they take the speed from the first two frames
+        let mut chaser = Chaser::new(&ChaserConfig::default());
+        chase(&encode(96_000.0, 40), &mut chaser);
+        assert_eq!(chaser.state(), LockState::Locked);
+        assert!((chaser.speed() - 0.5).abs() < 0.01, "{}", chaser.speed());
+    }
+}
@@ -26,6 +26,11 @@ pub enum LtcBgFlagsKind {
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct LtcBgFlags(u32);
 
 impl LtcBgFlags {
@@ -0,0 +1,248 @@
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiscontinuityKind {
+    // Moved forward by more than `max_dropped` frames
+    Jump,
+    // The same timecode twice in a row
+    Repeat,
+    // Moved against the direction of play
+    Backwards,
+    // Up to `max_dropped` frames are missing
+    Dropped,
+    // A label drop frame timecode skips, e.g. 00:01:00;00
+    DropFrameViolation,
+    // 23:59:59:xx followed by 00:00:00:00, the stream itself is continuous
+    MidnightWrap,
+}
+
+#[derive(Debug, Clone)]
+pub struct Discontinuity {
+    pub kind: DiscontinuityKind,
+    // Stream position of the frame the event was found at
+    pub position: i64,
+    pub expected: SMPTETimecode,
+    pub observed: SMPTETimecode,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ContinuityConfig {
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // Forward skips up to this many frames are reported as dropped frames instead of jumps
+    pub max_dropped: u32,
+}
+
+impl Default for ContinuityConfig {
+    fn default() -> Self {
+        ContinuityConfig {
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            max_dropped: 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ContinuityAnalyzer {
+    config: ContinuityConfig,
+    // Frames since midnight of the last valid frame
+    last: Option<i64>,
+}
+
+impl ContinuityAnalyzer {
+    pub fn new(config: &ContinuityConfig) -> Self {
+        ContinuityAnalyzer {
+            config: *config,
+            last: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    pub fn push(&mut self, frame: &LTCFrameExt) -> Vec<Discontinuity> {
+        let rate = self.config.rate;
+        let observed = frame.ltc().to_timecode(self.config.flags);
+        let position = frame.off_start();
+        let mut events = Vec::new();
+
+        let direction = if frame.reverse() { -1 } else { 1 };
+        let expected = self.last.map(|last| last + direction);
+        let expected_timecode = |frames: Option<i64>| {
+            frames.map_or_else(
+                || observed.clone(),
+                |frames| observed.with_frames(frames, rate),
+            )
+        };
+
+        // The label does not exist, there is nothing sensible to compare it to
+        if rate.is_dropped_label(observed.minutes(), observed.seconds(), observed.frame()) {
+            events.push(Discontinuity {
+                kind: DiscontinuityKind::DropFrameViolation,
+                position,
+                expected: expected_timecode(expected),
+                observed,
+            });
+            return events;
+        }
+
+        let frames = observed.to_frames(rate);
+        if let Some(expected) = expected {
+            let day = rate.frames_per_day();
+            // How far off the frame is in the direction of play, between -day/2 and day/2
+            let offset = ((frames - expected) * direction + day / 2).rem_euclid(day) - day / 2;
+            let kind = match offset {
+                0 if expected.rem_euclid(day) != expected => Some(DiscontinuityKind::MidnightWrap),
+                0 => None,
+                -1 => Some(DiscontinuityKind::Repeat),
+                offset if offset < -1 => Some(DiscontinuityKind::Backwards),
+                offset if offset <= self.config.max_dropped as i64 => {
+                    Some(DiscontinuityKind::Dropped)
+                }
+                _ => Some(DiscontinuityKind::Jump),
+            };
+            if let Some(kind) = kind {
+                events.push(Discontinuity {
+                    kind,
+                    position,
+                    expected: expected_timecode(Some(expected)),
+                    observed,
+                });
+            }
+        }
+        self.last = Some(frames);
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::encoder::{LTCEncoder, LTCEncoderConfig};
+    use crate::api::Timezone;
+
+    // Encodes one frame per label, without any drop frame correction, and analyzes the result
+    fn analyze(rate: FrameRate, labels: &[(u8, u8, u8, u8)]) -> Vec<(DiscontinuityKind, i64)> {
+        let mut encoder = LTCEncoder::try_new(&LTCEncoderConfig {
+            fps: rate.fps(),
+            standard: rate.standard(),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut frame = encoder.get_frame();
+        frame.set_dfbit(0);
+        encoder.set_frame(&frame);
+
+        let mut audio = Vec::new();
+        for &(hours, minutes, seconds, frame) in labels {
+            encoder.set_timecode(&SMPTETimecode::new(
+                Timezone::default(),
+                0,
+                0,
+                0,
+                hours,
+                minutes,
+                seconds,
+                frame,
+            ));
+            encoder.encode_frame();
+            let (buf, len) = encoder.get_buf_ref(true);
+            audio.extend_from_slice(&buf[..len]);
+        }
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio, 0);
+        let mut analyzer = ContinuityAnalyzer::new(&ContinuityConfig {
+            rate,
+            ..Default::default()
+        });
+        let mut events = Vec::new();
+        while let Some(frame) = decoder.read() {
+            for event in analyzer.push(&frame) {
+                assert_eq!(event.position, frame.off_start());
+                events.push((event.kind, event.observed.to_frames(rate)));
+            }
+        }
+        events
+    }
+
+    fn frames(seconds: u8, frame: u8) -> i64 {
+        FrameRate::FPS_25.hmsf_to_frames(1, 0, seconds, frame)
+    }
+
+    #[test]
+    fn test_continuous() {
+        let labels: Vec<_> = (0..25).map(|frame| (1, 0, 0, frame)).collect();
+        assert!(analyze(FrameRate::FPS_25, &labels).is_empty());
+    }
+
+    #[test]
+    fn test_discontinuities() {
+        let labels = [
+            (1, 0, 0, 0),
+            (1, 0, 0, 1),
+            (1, 0, 0, 2),
+            (1, 0, 0, 3),
+            (1, 0, 0, 3),
+            (1, 0, 0, 4),
+            (1, 0, 0, 6),
+            (1, 0, 0, 7),
+            (1, 0, 0, 20),
+            (1, 0, 0, 21),
+            (1, 0, 0, 10),
+            (1, 0, 0, 11),
+        ];
+        assert_eq!(
+            analyze(FrameRate::FPS_25, &labels),
+            [
+                (DiscontinuityKind::Repeat, frames(0, 3)),
+                (DiscontinuityKind::Dropped, frames(0, 6)),
+                (DiscontinuityKind::Jump, frames(0, 20)),
+                (DiscontinuityKind::Backwards, frames(0, 10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_midnight_wrap() {
+        let labels = [
+            (23, 59, 59, 21),
+            (23, 59, 59, 22),
+            (23, 59, 59, 23),
+            (23, 59, 59, 24),
+            (0, 0, 0, 0),
+            (0, 0, 0, 1),
+        ];
+        assert_eq!(
+            analyze(FrameRate::FPS_25, &labels),
+            [(DiscontinuityKind::MidnightWrap, 0)]
+        );
+    }
+
+    #[test]
+    fn test_drop_frame_violation() {
+        let rate = FrameRate::FPS_29_97_DF;
+        let labels = [
+            (0, 0, 59, 26),
+            (0, 0, 59, 27),
+            (0, 0, 59, 28),
+            (0, 0, 59, 29),
+            (0, 1, 0, 0),
+            (0, 1, 0, 1),
+            (0, 1, 0, 2),
+            (0, 1, 0, 3),
+        ];
+        let events = analyze(rate, &labels);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|(kind, _)| *kind == DiscontinuityKind::DropFrameViolation));
+    }
+}
@@ -0,0 +1,252 @@
+// Moving timecode and stream positions between frame rates. Real time conversions keep the
+// moment in time, e.g. an edit at 25 fps to the nearest frame at 24 fps. Frame for frame
+// conversions keep the frames and change the speed instead, which is what pull-up and
+// pull-down do: film shot at 24 fps and run at 23.976 (or at 25 for PAL) keeps every frame and
+// plays 0.1% slower (or 4% faster).
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Rounding {
+    #[default]
+    Nearest,
+    Floor,
+    Ceil,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ConversionMode {
+    #[default]
+    RealTime,
+    FrameForFrame,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RateConversion {
+    pub from: FrameRate,
+    pub to: FrameRate,
+    pub mode: ConversionMode,
+    pub rounding: Rounding,
+}
+
+fn div_round(numerator: i128, denominator: i128, rounding: Rounding) -> i128 {
+    match rounding {
+        Rounding::Nearest => (2 * numerator + denominator).div_euclid(2 * denominator),
+        Rounding::Floor => numerator.div_euclid(denominator),
+        Rounding::Ceil => -(-numerator).div_euclid(denominator),
+    }
+}
+
+fn round(value: f64, rounding: Rounding) -> i64 {
+    match rounding {
+        Rounding::Nearest => value.round() as i64,
+        Rounding::Floor => value.floor() as i64,
+        Rounding::Ceil => value.ceil() as i64,
+    }
+}
+
+impl RateConversion {
+    pub fn new(from: FrameRate, to: FrameRate, mode: ConversionMode) -> Self {
+        RateConversion {
+            from,
+            to,
+            mode,
+            rounding: Rounding::default(),
+        }
+    }
+
+    // How much faster the material plays after the conversion, e.g. 1.001 from 23.976 to 24
+    // frame for frame and 1 for real time conversions
+    pub fn speed(&self) -> f64 {
+        match self.mode {
+            ConversionMode::RealTime => 1.0,
+            ConversionMode::FrameForFrame => self.to.fps() / self.from.fps(),
+        }
+    }
+
+    // A frame count (or a duration in frames) at `from` to one at `to`
+    pub fn convert_frames(&self, frames: i64) -> i64 {
+        match self.mode {
+            ConversionMode::FrameForFrame => frames,
+            ConversionMode::RealTime => {
+                let (from, to) = (self.from, self.to);
+                // frames / from.fps * to.fps, kept as integers so long counts stay exact
+                div_round(
+                    frames as i128 * from.denominator() as i128 * to.numerator() as i128,
+                    from.numerator() as i128 * to.denominator() as i128,
+                    self.rounding,
+                ) as i64
+            }
+        }
+    }
+
+    // The time of day moves, the date stays. Frame for frame the labels depend on where the
+    // material starts unless both rates count frames the same way (e.g. 24 and 23.976), so
+    // other frame for frame conversions give None, use `convert_relative` for those.
+    pub fn convert(&self, timecode: &SMPTETimecode) -> Option<SMPTETimecode> {
+        if self.mode == ConversionMode::FrameForFrame
+            && self.from.frames_per_day() != self.to.frames_per_day()
+        {
+            return None;
+        }
+        let midnight = timecode.with_frames(0, self.from);
+        Some(self.convert_relative(timecode, &midnight))
+    }
+
+    // Converts the distance from `origin` and puts it after the same label at `to`, so that e.g.
+    // a reel starting at 01:00:00:00 still does after a frame for frame transfer
+    pub fn convert_relative(
+        &self,
+        timecode: &SMPTETimecode,
+        origin: &SMPTETimecode,
+    ) -> SMPTETimecode {
+        let offset = timecode.to_frames(self.from) - origin.to_frames(self.from);
+        let frames = origin.to_frames(self.to) + self.convert_frames(offset);
+        timecode.with_frames(frames, self.to)
+    }
+
+    pub fn convert_all(&self, timecodes: &[SMPTETimecode]) -> Option<Vec<SMPTETimecode>> {
+        timecodes
+            .iter()
+            .map(|timecode| self.convert(timecode))
+            .collect()
+    }
+
+    // A stream position before the conversion to the position of the same material after it,
+    // e.g. audio pulled down with the picture ends up 0.1% later
+    pub fn convert_samples(&self, samples: i64, from_rate: f64, to_rate: f64) -> i64 {
+        round(
+            samples as f64 * to_rate / from_rate / self.speed(),
+            self.rounding,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(timecode: &SMPTETimecode, rate: FrameRate) -> String {
+        timecode.to_time_string(rate.is_drop_frame())
+    }
+
+    #[test]
+    fn test_real_time() {
+        let mut conversion = RateConversion::new(
+            FrameRate::FPS_25,
+            FrameRate::FPS_24,
+            ConversionMode::RealTime,
+        );
+        let hour: SMPTETimecode = "01:00:00:00".parse().unwrap();
+        assert_eq!(
+            label(&conversion.convert(&hour).unwrap(), FrameRate::FPS_24),
+            "01:00:00:00"
+        );
+
+        // A frame at 25 is 0.96 frames at 24
+        for (rounding, forward, backward) in [
+            (Rounding::Nearest, 1, -1),
+            (Rounding::Floor, 0, -1),
+            (Rounding::Ceil, 1, 0),
+        ] {
+            conversion.rounding = rounding;
+            assert_eq!(conversion.convert_frames(1), forward);
+            assert_eq!(conversion.convert_frames(-1), backward);
+        }
+
+        // An hour of drop frame is 3.6 ms short of a real hour
+        let conversion = RateConversion::new(
+            FrameRate::FPS_29_97_DF,
+            FrameRate::FPS_25,
+            ConversionMode::RealTime,
+        );
+        let hour: SMPTETimecode = "01:00:00;00".parse().unwrap();
+        assert_eq!(
+            label(&conversion.convert(&hour).unwrap(), FrameRate::FPS_25),
+            "01:00:00:00"
+        );
+        let day = FrameRate::FPS_29_97_DF.frames_per_day();
+        assert_eq!(conversion.convert_frames(day), 2_159_998);
+
+        let list: Vec<SMPTETimecode> = ["00:00:10:00", "00:10:00:00"]
+            .iter()
+            .map(|timecode| timecode.parse().unwrap())
+            .collect();
+        let converted = RateConversion::new(
+            FrameRate::FPS_24,
+            FrameRate::FPS_29_97_DF,
+            ConversionMode::RealTime,
+        )
+        .convert_all(&list)
+        .unwrap();
+        let labels: Vec<_> = converted
+            .iter()
+            .map(|timecode| label(timecode, FrameRate::FPS_29_97_DF))
+            .collect();
+        // Drop frame labels stay within a frame of real time
+        assert_eq!(labels, ["00:00:10;00", "00:10:00;00"]);
+    }
+
+    #[test]
+    fn test_frame_for_frame() {
+        // Pull-down from film to NTSC video keeps the labels and runs slower
+        let pull_down = RateConversion::new(
+            FrameRate::FPS_24,
+            FrameRate::FPS_23_976,
+            ConversionMode::FrameForFrame,
+        );
+        let timecode: SMPTETimecode = "10:00:01:12".parse().unwrap();
+        assert_eq!(
+            label(
+                &pull_down.convert(&timecode).unwrap(),
+                FrameRate::FPS_23_976
+            ),
+            "10:00:01:12"
+        );
+        assert_eq!(
+            pull_down.convert_samples(48_000, 48_000.0, 48_000.0),
+            48_048
+        );
+        assert!((pull_down.speed() - 1.0 / 1.001).abs() < 1e-12);
+
+        // 25 to 24 keeps the frames, the labels after the origin move
+        let pal = RateConversion::new(
+            FrameRate::FPS_25,
+            FrameRate::FPS_24,
+            ConversionMode::FrameForFrame,
+        );
+        let origin: SMPTETimecode = "10:00:00:00".parse().unwrap();
+        let timecode: SMPTETimecode = "10:00:01:00".parse().unwrap();
+        assert_eq!(
+            label(&pal.convert_relative(&timecode, &origin), FrameRate::FPS_24),
+            "10:00:01:01"
+        );
+        assert_eq!(pal.convert_samples(48_000, 48_000.0, 48_000.0), 50_000);
+    }
+
+    #[test]
+    fn test_late_in_the_day() {
+        let timecode: SMPTETimecode = "23:00:00;00".parse().unwrap();
+        let to = FrameRate::FPS_23_976;
+
+        // 23 hours of drop frame labels are about 23 real hours, 23.976 labels run slow
+        let real_time = RateConversion::new(FrameRate::FPS_29_97_DF, to, ConversionMode::RealTime);
+        assert_eq!(
+            label(&real_time.convert(&timecode).unwrap(), to),
+            "22:58:37:05"
+        );
+
+        // Counted from midnight the frames would be more than a day at 24 frames a second
+        let transfer =
+            RateConversion::new(FrameRate::FPS_29_97_DF, to, ConversionMode::FrameForFrame);
+        assert!(transfer.convert(&timecode).is_none());
+        assert!(transfer
+            .convert_all(std::slice::from_ref(&timecode))
+            .is_none());
+        let origin: SMPTETimecode = "22:00:00;00".parse().unwrap();
+        assert_eq!(
+            label(&transfer.convert_relative(&timecode, &origin), to),
+            "23:14:55:12"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+// Film footage as feet and frames. 35mm 4-perf has 16 frames to the foot, 16mm has 40.
+use std::fmt::Display;
+
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+use crate::error::TimecodeError;
+
+pub const FRAMES_PER_FOOT_35MM: u32 = 16;
+pub const FRAMES_PER_FOOT_16MM: u32 = 40;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum FilmGauge {
+    // 4-perf
+    #[default]
+    Film35mm,
+    Film16mm,
+}
+
+impl FilmGauge {
+    pub fn frames_per_foot(&self) -> u32 {
+        match self {
+            FilmGauge::Film35mm => FRAMES_PER_FOOT_35MM,
+            FilmGauge::Film16mm => FRAMES_PER_FOOT_16MM,
+        }
+    }
+}
+
+// A frame count split into feet and frames, written as 1234+07. Negative footage counts back
+// from zero, -0+01 is the frame before 0+00.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FeetFrames {
+    frames: i64,
+    gauge: FilmGauge,
+}
+
+impl FeetFrames {
+    // `frames` has to be less than a foot, `negative` counts back from zero
+    pub fn new(
+        negative: bool,
+        feet: u32,
+        frames: u32,
+        gauge: FilmGauge,
+    ) -> Result<Self, TimecodeError> {
+        let frames_per_foot = gauge.frames_per_foot();
+        if frames >= frames_per_foot {
+            return Err(TimecodeError::ParseFeetFrames);
+        }
+        let total = feet as i64 * frames_per_foot as i64 + frames as i64;
+        Ok(FeetFrames::from_frames(
+            if negative { -total } else { total },
+            gauge,
+        ))
+    }
+
+    pub fn from_frames(frames: i64, gauge: FilmGauge) -> Self {
+        FeetFrames { frames, gauge }
+    }
+
+    pub fn to_frames(&self) -> i64 {
+        self.frames
+    }
+
+    pub fn gauge(&self) -> FilmGauge {
+        self.gauge
+    }
+
+    pub fn frames_per_foot(&self) -> u32 {
+        self.gauge.frames_per_foot()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.frames < 0
+    }
+
+    // Whole feet, without the sign
+    pub fn feet(&self) -> u64 {
+        self.frames.unsigned_abs() / self.frames_per_foot() as u64
+    }
+
+    // Frames past the last whole foot, without the sign
+    pub fn frames(&self) -> u32 {
+        (self.frames.unsigned_abs() % self.frames_per_foot() as u64) as u32
+    }
+
+    // Parses FEET+FRAMES, e.g. 1234+07, with an optional leading minus
+    pub fn parse(s: &str, gauge: FilmGauge) -> Result<Self, TimecodeError> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (feet, frames) = s.split_once('+').ok_or(TimecodeError::ParseFeetFrames)?;
+        let digits = |field: &str| !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit());
+        if !digits(feet) || !digits(frames) {
+            return Err(TimecodeError::ParseFeetFrames);
+        }
+        let feet: i64 = feet.parse().map_err(|_| TimecodeError::ParseFeetFrames)?;
+        let frames: u32 = frames.parse().map_err(|_| TimecodeError::ParseFeetFrames)?;
+        if frames >= gauge.frames_per_foot() {
+            return Err(TimecodeError::ParseFeetFrames);
+        }
+        let total = feet
+            .checked_mul(gauge.frames_per_foot() as i64)
+            .and_then(|total| total.checked_add(frames as i64))
+            .ok_or(TimecodeError::ParseFeetFrames)?;
+        Ok(FeetFrames::from_frames(
+            if negative { -total } else { total },
+            gauge,
+        ))
+    }
+}
+
+impl Display for FeetFrames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}+{:02}",
+            if self.is_negative() { "-" } else { "" },
+            self.feet(),
+            self.frames()
+        )
+    }
+}
+
+// A key number as printed along the film edge, e.g. KJ 23 1234 5678+07: a prefix naming the
+// manufacturer, emulsion and roll, then the footage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyKode {
+    pub prefix: String,
+    pub footage: FeetFrames,
+}
+
+impl KeyKode {
+    // The footage is the last word, everything before it is the prefix
+    pub fn parse(s: &str, gauge: FilmGauge) -> Result<Self, TimecodeError> {
+        let s = s.trim();
+        let (prefix, footage) = s.rsplit_once(char::is_whitespace).unwrap_or(("", s));
+        Ok(KeyKode {
+            prefix: prefix.trim_end().to_owned(),
+            footage: FeetFrames::parse(footage, gauge)?,
+        })
+    }
+
+    // The same roll `frames` further along, None if the footage doesn't fit in an i64
+    pub fn offset(&self, frames: i64) -> Option<KeyKode> {
+        Some(KeyKode {
+            prefix: self.prefix.clone(),
+            footage: FeetFrames::from_frames(
+                self.footage.to_frames().checked_add(frames)?,
+                self.footage.gauge(),
+            ),
+        })
+    }
+}
+
+impl Display for KeyKode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "{}", self.footage)
+        } else {
+            write!(f, "{} {}", self.prefix, self.footage)
+        }
+    }
+}
+
+// Ties footage to timecode: `origin` is the timecode of the frame at `start`, e.g. the first
+// frame of a reel at 0+00, or a key number read off the film
+#[derive(Debug, Clone)]
+pub struct FilmReel {
+    pub rate: FrameRate,
+    pub origin: SMPTETimecode,
+    pub start: FeetFrames,
+}
+
+// Both directions give None when the footage is too far out to count in an i64
+impl FilmReel {
+    pub fn to_feet_frames(&self, timecode: &SMPTETimecode) -> Option<FeetFrames> {
+        let offset = timecode.to_frames(self.rate) - self.origin.to_frames(self.rate);
+        Some(FeetFrames::from_frames(
+            self.start.to_frames().checked_add(offset)?,
+            self.start.gauge(),
+        ))
+    }
+
+    pub fn to_timecode(&self, footage: &FeetFrames) -> Option<SMPTETimecode> {
+        let offset = footage.to_frames().checked_sub(self.start.to_frames())?;
+        let frames = self.origin.to_frames(self.rate).checked_add(offset)?;
+        Some(self.origin.with_frames(frames, self.rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feet_frames() {
+        let footage = FeetFrames::from_frames(16 * 90 + 7, FilmGauge::Film35mm);
+        assert_eq!((footage.feet(), footage.frames()), (90, 7));
+        assert_eq!(footage.to_string(), "90+07");
+        let footage = FeetFrames::from_frames(40 * 36 + 39, FilmGauge::Film16mm);
+        assert_eq!(footage.to_string(), "36+39");
+        assert_eq!(
+            FeetFrames::new(false, 36, 39, FilmGauge::Film16mm).unwrap(),
+            footage
+        );
+        assert!(FeetFrames::new(false, 36, 40, FilmGauge::Film16mm).is_err());
+
+        let back = FeetFrames::from_frames(-1, FilmGauge::Film35mm);
+        assert_eq!(back.to_string(), "-0+01");
+        assert_eq!(
+            FeetFrames::new(true, 0, 1, FilmGauge::Film35mm).unwrap(),
+            back
+        );
+        let far = FeetFrames::from_frames(i64::MIN, FilmGauge::Film35mm);
+        assert_eq!(far.feet(), 1 << 59);
+        for text in ["0+00", "90+07", "-0+01", "-12+15", "1234+00"] {
+            let footage = FeetFrames::parse(text, FilmGauge::Film35mm).unwrap();
+            assert_eq!(footage.to_string(), text);
+        }
+        assert_eq!(
+            FeetFrames::parse("10+20", FilmGauge::Film16mm)
+                .unwrap()
+                .to_frames(),
+            420
+        );
+        for text in [
+            "10+16",
+            "10",
+            "+01",
+            "10+",
+            "1a+01",
+            "10+-1",
+            "9223372036854775807+00",
+            "576460752303423488+00",
+        ] {
+            assert!(
+                FeetFrames::parse(text, FilmGauge::Film35mm).is_err(),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_kode() {
+        let key = KeyKode::parse("KJ 23 1234 5678+07", FilmGauge::Film35mm).unwrap();
+        assert_eq!(key.prefix, "KJ 23 1234");
+        assert_eq!(key.footage.to_frames(), 5678 * 16 + 7);
+        assert_eq!(key.offset(9).unwrap().to_string(), "KJ 23 1234 5679+00");
+
+        // The largest footage parse takes, a frame further doesn't fit
+        let last = KeyKode::parse("KJ 576460752303423487+15", FilmGauge::Film35mm).unwrap();
+        assert_eq!(last.footage.to_frames(), i64::MAX);
+        assert!(last.offset(1).is_none());
+        assert_eq!(
+            last.offset(-16).unwrap().to_string(),
+            "KJ 576460752303423486+15"
+        );
+        let first = KeyKode::parse("-576460752303423487+15", FilmGauge::Film35mm).unwrap();
+        assert!(first.offset(-2).is_none());
+        assert_eq!(
+            KeyKode::parse("12+00", FilmGauge::Film35mm)
+                .unwrap()
+                .to_string(),
+            "12+00"
+        );
+    }
+
+    #[test]
+    fn test_film_reel() {
+        let reel = FilmReel {
+            rate: FrameRate::FPS_24,
+            origin: "01:00:00:00".parse().unwrap(),
+            start: FeetFrames::parse("12+00", FilmGauge::Film35mm).unwrap(),
+        };
+        // A minute of 35mm at 24 fps is 90 feet
+        let timecode: SMPTETimecode = "01:01:00:00".parse().unwrap();
+        let footage = reel.to_feet_frames(&timecode).unwrap();
+        assert_eq!(footage.to_string(), "102+00");
+        assert_eq!(
+            reel.to_timecode(&footage).unwrap().to_time_string(false),
+            "01:01:00:00"
+        );
+        let before: SMPTETimecode = "00:59:59:23".parse().unwrap();
+        assert_eq!(reel.to_feet_frames(&before).unwrap().to_string(), "11+15");
+
+        // Footage lowest the parse limits
+        let highest = FeetFrames::parse("576460752303423487+15", FilmGauge::Film35mm).unwrap();
+        let lowest = FeetFrames::parse("-576460752303423487+15", FilmGauge::Film35mm).unwrap();
+        let late = FilmReel {
+            start: highest,
+            ..reel.clone()
+        };
+        assert!(late.to_feet_frames(&timecode).is_none());
+        assert_eq!(
+            late.to_feet_frames(&before).unwrap().to_frames(),
+            i64::MAX - 1
+        );
+        assert!(late.to_timecode(&lowest).is_none());
+        assert!(reel.to_timecode(&lowest).is_none());
+        let early = FilmReel {
+            start: lowest,
+            ..reel
+        };
+        let earlier: SMPTETimecode = "00:59:59:22".parse().unwrap();
+        assert!(early.to_feet_frames(&earlier).is_none());
+        assert!(early.to_timecode(&highest).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use super::rate::FrameRate;
 use super::LTCTVStandard;
 use super::SMPTETimecode;
 use crate::api::consts;
@@ -22,6 +23,51 @@ impl LTCFrame {
     pub fn dfbit(&self) -> u32 {
         self.inner_raw.dfbit()
     }
+    pub fn set_dfbit(&mut self, dfbit: u32) {
+        self.inner_raw.set_dfbit(dfbit)
+    }
+    pub fn sync_word(&self) -> u16 {
+        self.inner_raw.sync_word() as u16
+    }
+}
+
+pub const LTC_SYNC_WORD: u16 = 0xBFFC;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrameField {
+    Frames,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrameViolation {
+    // The frame has an odd number of ones, `bit` is where the standard keeps the parity bit
+    Parity { bit: u32 },
+    SyncWord(u16),
+    // A BCD units digit above 9
+    InvalidDigit { field: FrameField, digit: u32 },
+    // A value the field can't take, e.g. 65 minutes or frame 27 at 25 fps
+    OutOfRange { field: FrameField, value: u32 },
+    // A frame number drop frame timecode skips, e.g. 00:01:00;00
+    DroppedFrameNumber,
+}
+
+impl std::fmt::Display for FrameViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FrameViolation::Parity { bit } => write!(f, "Parity error (bit {})", bit),
+            FrameViolation::SyncWord(word) => write!(f, "Invalid sync word {:#06X}", word),
+            FrameViolation::InvalidDigit { field, digit } => {
+                write!(f, "Invalid BCD digit {} in {:?}", digit, field)
+            }
+            FrameViolation::OutOfRange { field, value } => {
+                write!(f, "{:?} out of range: {}", field, value)
+            }
+            FrameViolation::DroppedFrameNumber => write!(f, "Frame number skipped by drop frame"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -34,6 +80,11 @@ impl LTCFrameExt {
     pub fn ltc(&self) -> LTCFrame {
         unsafe { *self.inner_unsafe_ptr }.ltc.into()
     }
+    pub fn set_ltc(&self, ltc: LTCFrame) {
+        unsafe {
+            (*self.inner_unsafe_ptr).ltc = ltc.inner_raw;
+        }
+    }
     pub fn off_start(&self) -> i64 {
         unsafe { *self.inner_unsafe_ptr }.off_start
     }
@@ -231,6 +282,140 @@ impl LTCFrame {
         unsafe { raw::ltc_frame_parse_bcg_flags(&mut inner_raw, standard.to_raw()) }.into()
     }
 
+    // Checks a received frame, an empty list means it is fine
+    pub fn validate(&self, standard: LTCTVStandard) -> Vec<FrameViolation> {
+        let frame = &self.inner_raw;
+        let mut violations = Vec::new();
+
+        if self.sync_word() != LTC_SYNC_WORD {
+            violations.push(FrameViolation::SyncWord(self.sync_word()));
+        }
+
+        // The parity bit makes the number of ones in the whole frame even
+        let ones: u32 = self.to_bytes().iter().map(|b| b.count_ones()).sum();
+        if !ones.is_multiple_of(2) {
+            violations.push(FrameViolation::Parity {
+                bit: parity_bit(standard) as u32,
+            });
+        }
+
+        let timebase = match standard {
+            LTCTVStandard::LTCTV_FILM_24 => 24,
+            LTCTVStandard::LTCTV_625_50 => 25,
+            _ => 30,
+        };
+        let fields = [
+            (
+                FrameField::Frames,
+                frame.frame_tens(),
+                frame.frame_units(),
+                timebase,
+            ),
+            (
+                FrameField::Seconds,
+                frame.secs_tens(),
+                frame.secs_units(),
+                60,
+            ),
+            (
+                FrameField::Minutes,
+                frame.mins_tens(),
+                frame.mins_units(),
+                60,
+            ),
+            (
+                FrameField::Hours,
+                frame.hours_tens(),
+                frame.hours_units(),
+                24,
+            ),
+        ];
+        for (field, tens, units, limit) in fields {
+            if units > 9 {
+                violations.push(FrameViolation::InvalidDigit {
+                    field,
+                    digit: units,
+                });
+            } else if tens * 10 + units >= limit {
+                violations.push(FrameViolation::OutOfRange {
+                    field,
+                    value: tens * 10 + units,
+                });
+            }
+        }
+
+        // Frames 0 and 1 of every minute but each tenth do not exist in drop frame
+        let minutes = frame.mins_tens() * 10 + frame.mins_units();
+        if frame.dfbit() != 0
+            && timebase == 30
+            && !minutes.is_multiple_of(10)
+            && frame.secs_tens() == 0
+            && frame.secs_units() == 0
+            && frame.frame_tens() == 0
+            && frame.frame_units() < 2
+        {
+            violations.push(FrameViolation::DroppedFrameNumber);
+        }
+
+        violations
+    }
+
+    // `validate` for LTC running at `rate`. Above 30 fps the parity bit carries the pair flag
+    // instead, so parity isn't checked there.
+    pub fn validate_rate(&self, rate: FrameRate) -> Vec<FrameViolation> {
+        let mut violations = self.validate(rate.standard());
+        if rate.is_high_frame_rate() {
+            violations.retain(|violation| !matches!(violation, FrameViolation::Parity { .. }));
+        }
+        violations
+    }
+
+    // The 80 bits of the frame in the order they are sent
+    pub fn to_bytes(&self) -> [u8; 10] {
+        let mut bytes = [0; 10];
+        // SAFETY: the bitfields are the first 10 bytes of the frame, libltc reads them the same way
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                &self.inner_raw as *const raw::LTCFrame as *const u8,
+                bytes.as_mut_ptr(),
+                bytes.len(),
+            );
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; 10]) -> Self {
+        let mut frame = Self::new();
+        // SAFETY: the bitfields are the first 10 bytes of the frame, see `to_bytes`
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                &mut frame.inner_raw as *mut raw::LTCFrame as *mut u8,
+                bytes.len(),
+            );
+        }
+        frame
+    }
+
+    // Above 30 fps SMPTE 12-1 marks the second frame of a frame pair in the bit that evens out
+    // the parity at lower rates, bit 27 or bit 59 at 25 fps. `set_parity` writes the same bit, so
+    // frames carrying the flag are built and incremented with `LTC_NO_PARITY`.
+    pub fn pair_bit(&self, standard: LTCTVStandard) -> bool {
+        let bit = parity_bit(standard);
+        self.to_bytes()[bit / 8] & (1 << (bit % 8)) != 0
+    }
+
+    pub fn set_pair_bit(&mut self, standard: LTCTVStandard, second: bool) {
+        let bit = parity_bit(standard);
+        let mut bytes = self.to_bytes();
+        if second {
+            bytes[bit / 8] |= 1 << (bit % 8);
+        } else {
+            bytes[bit / 8] &= !(1 << (bit % 8));
+        }
+        *self = LTCFrame::from_bytes(bytes);
+    }
+
     pub fn get_user_bits(&self) -> u32 {
         let mut inner_raw = self.inner_raw;
         // SAFETY: The function is assumed to only read self (the frame)
@@ -238,7 +423,92 @@ impl LTCFrame {
     }
 }
 
+// The bit that evens out the parity
+fn parity_bit(standard: LTCTVStandard) -> usize {
+    match standard {
+        LTCTVStandard::LTCTV_625_50 => 59,
+        _ => 27,
+    }
+}
+
 pub fn calc_frame_alignment(samples_per_frame: f64, standard: LTCTVStandard) -> i64 {
     // SAFETY: The function is assumed to be pure
     unsafe { raw::ltc_frame_alignment(samples_per_frame, standard.to_raw()) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Timezone;
+
+    fn frame(minutes: u8, seconds: u8, frame: u8, standard: LTCTVStandard) -> LTCFrame {
+        let timecode = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 1, minutes, seconds, frame);
+        let mut frame = LTCFrame::from_timecode(&timecode, standard, LtcBgFlags::default());
+        frame.set_parity(standard);
+        frame
+    }
+
+    #[test]
+    fn test_validate_parity() {
+        for (standard, bit) in [
+            (LTCTVStandard::LTCTV_525_60, 27),
+            (LTCTVStandard::LTCTV_625_50, 59),
+            (LTCTVStandard::LTCTV_1125_60, 27),
+            (LTCTVStandard::LTCTV_FILM_24, 27),
+        ] {
+            let mut frame = frame(2, 3, 4, standard);
+            assert_eq!(frame.validate(standard), []);
+            frame.inner_raw.set_user1(1);
+            assert_eq!(frame.validate(standard), [FrameViolation::Parity { bit }]);
+        }
+    }
+
+    #[test]
+    fn test_validate_fields() {
+        let standard = LTCTVStandard::LTCTV_625_50;
+        let mut frame = frame(0, 0, 0, standard);
+        frame.inner_raw.set_frame_tens(2);
+        frame.inner_raw.set_frame_units(7);
+        frame.inner_raw.set_mins_tens(6);
+        frame.inner_raw.set_hours_units(12);
+        frame.inner_raw.set_sync_word(0x1234);
+        frame.set_parity(standard);
+        assert_eq!(
+            frame.validate(standard),
+            [
+                FrameViolation::SyncWord(0x1234),
+                FrameViolation::OutOfRange {
+                    field: FrameField::Frames,
+                    value: 27
+                },
+                FrameViolation::OutOfRange {
+                    field: FrameField::Minutes,
+                    value: 60
+                },
+                FrameViolation::InvalidDigit {
+                    field: FrameField::Hours,
+                    digit: 12
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_drop_frame() {
+        let standard = LTCTVStandard::LTCTV_525_60;
+        let mut frame = frame(1, 0, 0, standard);
+        frame.set_dfbit(1);
+        frame.inner_raw.set_frame_units(0);
+        frame.set_parity(standard);
+        assert_eq!(
+            frame.validate(standard),
+            [FrameViolation::DroppedFrameNumber]
+        );
+
+        // Every tenth minute keeps its first two frames
+        let mut frame = self::frame(10, 0, 0, standard);
+        frame.set_dfbit(1);
+        frame.set_parity(standard);
+        assert_eq!(frame.validate(standard), []);
+    }
+}
@@ -0,0 +1,517 @@
+use std::io;
+
+use super::consts::{LtcBgFlags, SampleType};
+use super::encoder::{LTCEncoder, LTCEncoderConfig};
+use super::frame::calc_frame_alignment;
+use super::rate::FrameRate;
+use super::sample::Sample;
+use super::SMPTETimecode;
+use crate::error::LTCEncoderError;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LTCGeneratorConfig {
+    pub sample_rate: f64,
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // Output level in dBFS
+    pub volume: f64,
+    // Rise time of the signal in microseconds
+    pub filter: f64,
+}
+
+impl Default for LTCGeneratorConfig {
+    fn default() -> Self {
+        LTCGeneratorConfig {
+            sample_rate: 48_000.0,
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            volume: -3.0,
+            filter: 40.0,
+        }
+    }
+}
+
+// Continuous LTC audio on top of LTCEncoder. Frames are encoded one at a time into the
+// encoder's buffer and handed out in whatever amounts the caller asks for. Every frame is cut
+// or padded to end exactly where the frame rate puts it, so fractional rates never drift.
+#[derive(Debug)]
+pub struct LTCGenerator {
+    config: LTCGeneratorConfig,
+    encoder: LTCEncoder,
+    // Frames since midnight of the frame in the encoder buffer, or of the next frame to encode
+    frames: i64,
+    // Frames handed out since the last `set_timecode`, which is where the exact lengths start
+    index: i64,
+    // Length of the frame in the encoder buffer and how much of it was handed out
+    len: usize,
+    consumed: usize,
+}
+
+// An encoder set up for `config`, shared by the generators
+pub(crate) fn encoder_for(config: &LTCGeneratorConfig) -> Result<LTCEncoder, LTCEncoderError> {
+    // Frame tens only go up to 3, faster rates count frame pairs at half the rate
+    if config.rate.is_high_frame_rate() {
+        return Err(LTCEncoderError::HighFrameRate);
+    }
+    let mut encoder = LTCEncoder::try_new(&LTCEncoderConfig {
+        sample_rate: config.sample_rate,
+        fps: config.rate.fps(),
+        standard: config.rate.standard(),
+        flags: config.flags,
+    })?;
+    encoder.set_volume(config.volume)?;
+    encoder.set_filter(config.filter);
+
+    // libltc turns drop frame on for any 29.97 rate
+    let mut frame = encoder.get_frame();
+    frame.set_dfbit(config.rate.is_drop_frame() as u32);
+    encoder.set_frame(&frame);
+    Ok(encoder)
+}
+
+impl LTCGenerator {
+    pub fn try_new(
+        config: &LTCGeneratorConfig,
+        start: &SMPTETimecode,
+    ) -> Result<Self, LTCEncoderError> {
+        let encoder = encoder_for(config)?;
+        let mut generator = LTCGenerator {
+            config: *config,
+            encoder,
+            frames: 0,
+            index: 0,
+            len: 0,
+            consumed: 0,
+        };
+        generator.set_timecode(start);
+        Ok(generator)
+    }
+
+    pub fn config(&self) -> &LTCGeneratorConfig {
+        &self.config
+    }
+
+    // Starts over at `timecode`, dropping whatever is left of the current frame
+    pub fn set_timecode(&mut self, timecode: &SMPTETimecode) {
+        self.encoder.buffer_flush();
+        self.encoder.set_timecode(timecode);
+        self.frames = timecode.to_frames(self.config.rate);
+        self.index = 0;
+        self.len = 0;
+        self.consumed = 0;
+    }
+
+    // Lines the output up so that the LTC frame `timecode` starts at `sample`, counting the
+    // next sample handed out as sample 0. The frame around sample 0 is cut so the signal is in
+    // phase from there.
+    pub fn set_anchor(
+        &mut self,
+        timecode: &SMPTETimecode,
+        sample: i64,
+    ) -> Result<(), LTCEncoderError> {
+        let rate = self.config.rate;
+        let sample_rate = self.config.sample_rate;
+
+        // The frame relative to the anchor that sample 0 falls into
+        let mut index = (-sample as f64 / rate.samples_per_frame(sample_rate)).floor() as i64;
+        while sample + rate.frame_start(index + 1, sample_rate) <= 0 {
+            index += 1;
+        }
+        while sample + rate.frame_start(index, sample_rate) > 0 {
+            index -= 1;
+        }
+
+        let frames = timecode.to_frames(rate) + index;
+        self.set_timecode(&timecode.with_frames(frames, rate));
+        self.index = index;
+        self.skip_samples(-(sample + rate.frame_start(index, sample_rate)) as usize)
+    }
+
+    // Like `set_anchor`, but `sample` is where the video frame `timecode` starts. The LTC frame
+    // follows `video_alignment()` samples later.
+    pub fn set_video_anchor(
+        &mut self,
+        timecode: &SMPTETimecode,
+        sample: i64,
+    ) -> Result<(), LTCEncoderError> {
+        self.set_anchor(timecode, sample + self.video_alignment())
+    }
+
+    // Samples an LTC frame starts after the video frame it belongs to
+    pub fn video_alignment(&self) -> i64 {
+        let samples_per_frame = self.config.rate.samples_per_frame(self.config.sample_rate);
+        calc_frame_alignment(samples_per_frame, self.config.rate.standard())
+    }
+
+    // The timecode of the next sample handed out
+    pub fn timecode(&self) -> SMPTETimecode {
+        let mut timecode = self.encoder.get_timecode();
+        if self.consumed < self.len {
+            // The encoder has already moved on to the following frame
+            timecode = timecode.with_frames(self.frames, self.config.rate);
+        }
+        timecode
+    }
+
+    // Fractional frames since midnight of the next sample handed out
+    pub fn position(&self) -> f64 {
+        if self.consumed < self.len {
+            self.frames as f64 + self.consumed as f64 / self.len as f64
+        } else {
+            self.frames as f64 + (self.len > 0) as i64 as f64
+        }
+    }
+
+    pub fn set_user_bits(&mut self, data: u32) {
+        self.encoder.set_user_bits(data);
+    }
+
+    // Sets the pair flag on every frame from here on, which needs `LTC_NO_PARITY` in the flags
+    pub(crate) fn set_pair_bit(&mut self, second: bool) {
+        let mut frame = self.encoder.get_frame();
+        frame.set_pair_bit(self.config.rate.standard(), second);
+        self.encoder.set_frame(&frame);
+    }
+
+    // Ends the signal after the samples handed out so far and returns the samples that bring it
+    // to rest. Call it on a frame boundary, the rest of a started frame is dropped.
+    pub fn finish(&mut self) -> Result<&[SampleType], LTCEncoderError> {
+        if self.len > 0 {
+            self.frames += 1;
+            self.index += 1;
+        }
+        self.len = 0;
+        self.consumed = 0;
+        self.encoder.buffer_flush();
+        self.encoder.end_encode()?;
+        let (buf, len) = self.encoder.get_buf_ref(false);
+        Ok(&buf[..len])
+    }
+
+    // What `finish` returned, until the generator is used again
+    pub(crate) fn buffered(&self) -> &[SampleType] {
+        let (buf, len) = self.encoder.get_buf_ref(false);
+        &buf[..len]
+    }
+
+    pub fn skip_samples(&mut self, mut samples: usize) -> Result<(), LTCEncoderError> {
+        while samples > 0 {
+            let available = self.available()?;
+            let n = samples.min(available);
+            self.consumed += n;
+            samples -= n;
+        }
+        Ok(())
+    }
+
+    // Picks up where the last call stopped, nothing is allocated
+    pub fn fill<S: Sample>(&mut self, out: &mut [S]) -> Result<(), LTCEncoderError> {
+        let mut written = 0;
+        while written < out.len() {
+            let available = self.available()?;
+            let n = (out.len() - written).min(available);
+            let (buf, encoded) = self.encoder.get_buf_ref(false);
+            let start = self.consumed;
+            let split = (start + n).min(encoded).max(start);
+            for (out, &sample) in out[written..].iter_mut().zip(&buf[start..split]) {
+                *out = S::from_ltc(sample);
+            }
+            // The encoder came up a sample short of the exact length, hold the last level
+            out[written + split - start..written + n].fill(S::from_ltc(buf[encoded - 1]));
+            self.consumed += n;
+            written += n;
+        }
+        Ok(())
+    }
+
+    // Samples left of the current frame, encoding the next one if it ran out
+    fn available(&mut self) -> Result<usize, LTCEncoderError> {
+        if self.consumed == self.len {
+            if self.len > 0 {
+                self.frames += 1;
+                self.index += 1;
+            }
+            self.encoder.buffer_flush();
+            self.encoder.encode_frame();
+            let rate = self.config.rate;
+            let sample_rate = self.config.sample_rate;
+            self.len = (rate.frame_start(self.index + 1, sample_rate)
+                - rate.frame_start(self.index, sample_rate)) as usize;
+            self.consumed = 0;
+            self.encoder.inc_timecode()?;
+        }
+        Ok(self.len - self.consumed)
+    }
+}
+
+// Endless, use `take` to bound it. Stops early only if the encoder fails.
+impl Iterator for LTCGenerator {
+    type Item = SampleType;
+
+    fn next(&mut self) -> Option<SampleType> {
+        let mut sample = [0];
+        self.fill(&mut sample).ok()?;
+        Some(sample[0])
+    }
+}
+
+// 8 bit unsigned mono PCM
+impl io::Read for LTCGenerator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::Timezone;
+    use std::io::Read;
+
+    #[test]
+    fn test_generator_fill() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let mut generator = LTCGenerator::try_new(&LTCGeneratorConfig::default(), &start).unwrap();
+
+        // Odd block sizes that never line up with the frames
+        let mut audio = vec![0; 20 * 1920];
+        for block in audio.chunks_mut(77) {
+            generator.fill(block).unwrap();
+        }
+        assert_eq!(
+            generator.position(),
+            start.to_frames(FrameRate::FPS_25) as f64 + 20.0
+        );
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio, 0);
+        let mut expected = start.to_frames(FrameRate::FPS_25);
+        let mut count = 0;
+        while let Some(frame) = decoder.read() {
+            let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+            if count == 0 {
+                expected = timecode.to_frames(FrameRate::FPS_25);
+            }
+            assert_eq!(timecode.to_frames(FrameRate::FPS_25), expected);
+            expected += 1;
+            count += 1;
+        }
+        assert!(count >= 18);
+    }
+
+    #[test]
+    fn test_generator_skip() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let mut generator = LTCGenerator::try_new(&LTCGeneratorConfig::default(), &start).unwrap();
+        generator.skip_samples(1920 + 480).unwrap();
+        assert_eq!(
+            generator.position(),
+            start.to_frames(FrameRate::FPS_25) as f64 + 1.25
+        );
+        assert_eq!(generator.timecode().frame(), 1);
+    }
+
+    #[test]
+    fn test_generator_high_frame_rate() {
+        let config = LTCGeneratorConfig {
+            rate: FrameRate::FPS_50,
+            ..Default::default()
+        };
+        assert!(matches!(
+            LTCGenerator::try_new(&config, &SMPTETimecode::default()),
+            Err(LTCEncoderError::HighFrameRate)
+        ));
+    }
+
+    // Sync words have to end where the exact frame rate puts them, however long it runs
+    #[test]
+    fn test_generator_exact_rate() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        for (rate, sample_rate) in [
+            (FrameRate::FPS_29_97, 48_000.0),
+            (FrameRate::FPS_29_97_DF, 48_000.0),
+            (FrameRate::FPS_23_976, 44_100.0),
+        ] {
+            let config = LTCGeneratorConfig {
+                sample_rate,
+                rate,
+                ..Default::default()
+            };
+            let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+            let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+            let first = start.to_frames(rate);
+            let mut offsets = Vec::new();
+            let mut audio = vec![0; 1000];
+            for i in 0..1200 {
+                generator.fill(&mut audio).unwrap();
+                decoder.write(&audio, i * 1000);
+                while let Some(frame) = decoder.read() {
+                    let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                    let index = timecode.to_frames(rate) - first;
+                    offsets.push(frame.off_end() - rate.frame_start(index + 1, sample_rate));
+                }
+            }
+            assert!(offsets.len() > 600);
+            let min = offsets.iter().min().unwrap();
+            let max = offsets.iter().max().unwrap();
+            assert!(max - min <= 1, "{rate:?} {min} {max}");
+        }
+
+        // 8008 samples every 5 frames
+        let config = LTCGeneratorConfig {
+            rate: FrameRate::FPS_29_97,
+            ..Default::default()
+        };
+        let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+        let mut audio = vec![0u8; 8008 * 100];
+        generator.fill(&mut audio).unwrap();
+        assert_eq!(
+            generator.position(),
+            start.to_frames(FrameRate::FPS_29_97) as f64 + 500.0
+        );
+    }
+
+    #[test]
+    fn test_generator_stream() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let config = LTCGeneratorConfig::default();
+        let mut expected = vec![0; 5000];
+        LTCGenerator::try_new(&config, &start)
+            .unwrap()
+            .fill(&mut expected)
+            .unwrap();
+
+        let generator = LTCGenerator::try_new(&config, &start).unwrap();
+        let samples: Vec<_> = Iterator::take(generator, 5000).collect();
+        assert_eq!(samples, expected);
+
+        let mut bytes = Vec::new();
+        let generator = LTCGenerator::try_new(&config, &start).unwrap();
+        Read::take(generator, 5000).read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_generator_sample_types() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let config = LTCGeneratorConfig::default();
+        let generator = || LTCGenerator::try_new(&config, &start).unwrap();
+
+        // Callback sized blocks that never line up with the frames
+        let mut bytes = vec![0u8; 10 * 1920];
+        let mut floats = vec![0.0f32; 10 * 1920];
+        let mut shorts = vec![0i16; 10 * 1920];
+        let (mut a, mut b, mut c) = (generator(), generator(), generator());
+        for block in bytes.chunks_mut(64) {
+            a.fill(block).unwrap();
+        }
+        for block in floats.chunks_mut(64) {
+            b.fill(block).unwrap();
+        }
+        for block in shorts.chunks_mut(128) {
+            c.fill(block).unwrap();
+        }
+        for ((&byte, &float), &short) in bytes.iter().zip(&floats).zip(&shorts) {
+            assert_eq!(float, f32::from_ltc(byte));
+            assert_eq!(short, i16::from_ltc(byte));
+        }
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write_float(&floats, 0);
+        let mut count = 0;
+        while let Some(frame) = decoder.read() {
+            let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+            assert_eq!(timecode.hours(), 10);
+            count += 1;
+        }
+        assert!(count >= 8);
+    }
+
+    // (frames after `start`, off_end minus where the frame should end) of every decoded frame
+    fn frame_ends(
+        generator: &mut LTCGenerator,
+        start: &SMPTETimecode,
+        anchor: i64,
+    ) -> Vec<(i64, i64)> {
+        let rate = generator.config().rate;
+        let sample_rate = generator.config().sample_rate;
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut ends = Vec::new();
+        let mut audio = vec![0u8; 1000];
+        for i in 0..30 {
+            generator.fill(&mut audio).unwrap();
+            decoder.write(&audio, i * 1000);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                let index = timecode.to_frames(rate) - start.to_frames(rate);
+                let end = anchor + rate.frame_start(index + 1, sample_rate);
+                ends.push((index, frame.off_end() - end));
+            }
+        }
+        ends
+    }
+
+    #[test]
+    fn test_generator_anchor() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let config = LTCGeneratorConfig {
+            rate: FrameRate::FPS_29_97,
+            ..Default::default()
+        };
+
+        // How late the decoder reports the end of a frame starting at sample 0
+        let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+        let (_, latency) = frame_ends(&mut generator, &start, 0)[0];
+
+        for anchor in [0, 137, 1601, 5000, -137, -4000] {
+            let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+            generator.set_anchor(&start, anchor).unwrap();
+            let ends = frame_ends(&mut generator, &start, anchor);
+            assert!(ends.len() > 15);
+            for (index, offset) in ends {
+                assert!((offset - latency).abs() <= 1, "{anchor} {index} {offset}");
+            }
+        }
+
+        // 525 line video puts the LTC frame 4 lines after the video frame
+        let alignment = generator.video_alignment();
+        assert_eq!(alignment, 12);
+        for anchor in [0, 137, -4000] {
+            let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+            generator.set_video_anchor(&start, anchor).unwrap();
+            let ends = frame_ends(&mut generator, &start, anchor + alignment);
+            assert!(ends.len() > 15);
+            for (index, offset) in ends {
+                assert!((offset - latency).abs() <= 1, "{anchor} {index} {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_generator_anchor_start() {
+        // The anchored frame starts right at the anchor
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let config = LTCGeneratorConfig {
+            rate: FrameRate::FPS_29_97,
+            ..Default::default()
+        };
+        let mut generator = LTCGenerator::try_new(&config, &start).unwrap();
+        generator.set_anchor(&start, 137).unwrap();
+        let mut audio = vec![0u8; 10_000];
+        generator.fill(&mut audio).unwrap();
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio, 0);
+        let mut starts = Vec::new();
+        while let Some(frame) = decoder.read() {
+            let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+            starts.push((timecode.to_time_string(false), frame.off_start()));
+        }
+        assert!(
+            starts.contains(&("10:00:00:00".to_owned(), 137)),
+            "{starts:?}"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// High frame rates per SMPTE 12-1. Above 30 fps the timecode label counts frame pairs, so
+// 60 fps runs LTC at 30 fps and both frames of a pair share a label. Every LTC frame lasts a
+// pair and names the frame it starts on: the label of its pair, plus the pair flag when that is
+// the second frame of the pair. A stream started on a second frame carries the flag
+// throughout. The flag takes the place of the parity bit, so high frame rate LTC is encoded
+// with `LTC_NO_PARITY` and checked with `LTCFrame::validate_rate`.
+use super::consts::{LtcBgFlags, LtcBgFlagsKind};
+use super::frame::{LTCFrame, LTCFrameExt};
+use super::generator::{LTCGenerator, LTCGeneratorConfig};
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+use crate::error::{LTCEncoderError, TimecodeError};
+
+fn check(rate: FrameRate) -> Result<(), TimecodeError> {
+    if rate.is_high_frame_rate() {
+        Ok(())
+    } else {
+        Err(TimecodeError::NotHighFrameRate)
+    }
+}
+
+// The label of the pair a frame belongs to, and whether it is the second frame of the pair
+pub fn to_frame_pair(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+) -> Result<(SMPTETimecode, bool), TimecodeError> {
+    check(rate)?;
+    let frames = timecode.to_frames(rate);
+    let pair = timecode.with_frames(frames.div_euclid(2), rate.pair_rate());
+    Ok((pair, frames.rem_euclid(2) == 1))
+}
+
+pub fn from_frame_pair(
+    pair: &SMPTETimecode,
+    second: bool,
+    rate: FrameRate,
+) -> Result<SMPTETimecode, TimecodeError> {
+    check(rate)?;
+    let frames = 2 * pair.to_frames(rate.pair_rate()) + second as i64;
+    Ok(pair.with_frames(frames, rate))
+}
+
+// The LTC frame starting on a high frame rate frame, with the pair flag set on a second frame
+pub fn hfr_ltc_frame(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+    flags: LtcBgFlags,
+) -> Result<LTCFrame, TimecodeError> {
+    let (pair, second) = to_frame_pair(timecode, rate)?;
+    let standard = rate.standard();
+    let mut frame = LTCFrame::from_timecode(&pair, standard, flags);
+    frame.set_dfbit(rate.is_drop_frame() as u32);
+    frame.set_pair_bit(standard, second);
+    Ok(frame)
+}
+
+// The high frame rate frame an LTC frame starts on, counting the pair flag
+pub fn hfr_timecode(
+    frame: &LTCFrame,
+    rate: FrameRate,
+    flags: LtcBgFlags,
+) -> Result<SMPTETimecode, TimecodeError> {
+    let pair = frame.to_timecode(flags);
+    from_frame_pair(&pair, frame.pair_bit(rate.standard()), rate)
+}
+
+// A generator for `config.rate` above 30 fps starting on `start`. It runs LTC at the pair rate
+// and sets the pair flag when `start` is the second frame of its pair.
+pub fn hfr_generator(
+    config: &LTCGeneratorConfig,
+    start: &SMPTETimecode,
+) -> Result<LTCGenerator, LTCEncoderError> {
+    let (pair, second) = to_frame_pair(start, config.rate)?;
+    let mut flags = config.flags;
+    flags.set(LtcBgFlagsKind::LTC_NO_PARITY);
+    let pair_config = LTCGeneratorConfig {
+        rate: config.rate.pair_rate(),
+        flags,
+        ..*config
+    };
+    let mut generator = LTCGenerator::try_new(&pair_config, &pair)?;
+    generator.set_pair_bit(second);
+    Ok(generator)
+}
+
+#[derive(Debug, Clone)]
+pub struct HFRFrame {
+    pub timecode: SMPTETimecode,
+    // First and last sample of the frame in the stream
+    pub start: i64,
+    pub end: i64,
+}
+
+// Splits a decoded LTC frame into the two frames it spans, in stream order. Played backwards
+// the later frame comes first.
+pub fn hfr_frames(
+    frame: &LTCFrameExt,
+    rate: FrameRate,
+    flags: LtcBgFlags,
+) -> Result<[HFRFrame; 2], TimecodeError> {
+    let (start, end) = (frame.off_start(), frame.off_end());
+    let middle = start + (end - start + 1) / 2;
+    let first = hfr_timecode(&frame.ltc(), rate, flags)?;
+    let second = first.with_frames(first.to_frames(rate) + 1, rate);
+    let (early, late) = if frame.reverse() {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    Ok([
+        HFRFrame {
+            timecode: early,
+            start,
+            end: middle - 1,
+        },
+        HFRFrame {
+            timecode: late,
+            start: middle,
+            end,
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+
+    #[test]
+    fn test_frame_pairs() {
+        assert_eq!(FrameRate::FPS_59_94_DF.pair_rate(), FrameRate::FPS_29_97_DF);
+        assert_eq!(FrameRate::FPS_50.pair_rate(), FrameRate::FPS_25);
+        assert!(to_frame_pair(&SMPTETimecode::default(), FrameRate::FPS_30).is_err());
+
+        for (rate, label) in [
+            (FrameRate::FPS_50, "10:00:00:49"),
+            (FrameRate::FPS_60, "10:00:00:59"),
+            (FrameRate::FPS_59_94_DF, "10:01:00:04"),
+        ] {
+            let timecode: SMPTETimecode = label.parse().unwrap();
+            let (pair, second) = to_frame_pair(&timecode, rate).unwrap();
+            assert_eq!(pair.frame(), timecode.frame() / 2);
+            assert_eq!(second, timecode.frame() % 2 == 1);
+            let back = from_frame_pair(&pair, second, rate).unwrap();
+            assert_eq!(back.to_frames(rate), timecode.to_frames(rate));
+
+            let ltc = hfr_ltc_frame(&timecode, rate, LtcBgFlags::default()).unwrap();
+            assert_eq!(ltc.pair_bit(rate.standard()), second);
+            assert_eq!(ltc.validate_rate(rate), []);
+            let decoded = hfr_timecode(&ltc, rate, LtcBgFlags::default()).unwrap();
+            assert_eq!(decoded.to_time_string(false), label);
+        }
+
+        // The flag stands in for parity, the frames before and after it are fine either way
+        for label in ["10:00:00:48", "10:00:00:49"] {
+            let timecode: SMPTETimecode = label.parse().unwrap();
+            let ltc = hfr_ltc_frame(&timecode, FrameRate::FPS_50, LtcBgFlags::default()).unwrap();
+            assert_eq!(ltc.validate_rate(FrameRate::FPS_50), []);
+        }
+    }
+
+    // Generates from `start`, decodes and splits the frames
+    fn round_trip(rate: FrameRate, start: &str) -> Vec<HFRFrame> {
+        let config = LTCGeneratorConfig {
+            rate,
+            ..Default::default()
+        };
+        let start: SMPTETimecode = start.parse().unwrap();
+        let mut generator = hfr_generator(&config, &start).unwrap();
+        assert_eq!(generator.config().rate, rate.pair_rate());
+        let mut audio = vec![0u8; 48_000];
+        generator.fill(&mut audio).unwrap();
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio, 0);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read() {
+            assert_eq!(frame.ltc().validate_rate(rate), []);
+            frames.extend(hfr_frames(&frame, rate, LtcBgFlags::default()).unwrap());
+        }
+        frames
+    }
+
+    #[test]
+    fn test_hfr_generator() {
+        let rate = FrameRate::FPS_50;
+        for start in ["01:00:00:00", "01:00:00:01"] {
+            let frames = round_trip(rate, start);
+            assert!(frames.len() >= 40);
+            // The first frame starts at sample 0, whichever frame of its pair it is
+            assert_eq!(frames[0].timecode.to_time_string(false), start);
+            assert!(frames[0].start <= 1, "{}", frames[0].start);
+            for pair in frames.windows(2) {
+                assert_eq!(
+                    pair[1].timecode.to_frames(rate) - pair[0].timecode.to_frames(rate),
+                    1
+                );
+                assert_eq!(pair[1].start, pair[0].end + 1);
+                assert!((959..=961).contains(&(pair[0].end - pair[0].start + 1)));
+            }
+        }
+
+        let frames = round_trip(FrameRate::FPS_59_94_DF, "00:00:59;59");
+        let labels: Vec<_> = frames[..4]
+            .iter()
+            .map(|frame| frame.timecode.to_time_string(true))
+            .collect();
+        assert_eq!(
+            labels,
+            ["00:00:59;59", "00:01:00;04", "00:01:00;05", "00:01:00;06"]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use super::chase::{Chaser, ChaserConfig, LockState};
+use super::frame::LTCFrameExt;
+use super::generator::{LTCGenerator, LTCGeneratorConfig};
+use super::sample::Sample;
+use super::SMPTETimecode;
+use crate::error::LTCEncoderError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum JamMode {
+    // Jam once, then run free no matter what comes in
+    #[default]
+    OneShot,
+    // Jam again whenever the incoming LTC no longer matches the generator
+    Continuous,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct JamSyncConfig {
+    pub generator: LTCGeneratorConfig,
+    pub mode: JamMode,
+    // Samples between a frame ending in the stream and the decoder reporting its end
+    pub latency: f64,
+    // Consecutive frames that have to agree before jamming
+    pub lock_frames: u32,
+    // Samples the generator may be off from the incoming LTC before a continuous re-jam
+    pub tolerance: f64,
+}
+
+impl Default for JamSyncConfig {
+    fn default() -> Self {
+        JamSyncConfig {
+            generator: LTCGeneratorConfig::default(),
+            mode: JamMode::default(),
+            latency: 0.0,
+            lock_frames: 4,
+            tolerance: 2.0,
+        }
+    }
+}
+
+// Jams a free running generator from decoded LTC. Input and output share one sample clock:
+// `push` takes frames decoded from the input stream and `fill` writes the output for the same
+// stream positions, starting where `set_position` put it.
+#[derive(Debug)]
+pub struct JamSync {
+    config: JamSyncConfig,
+    chaser: Chaser,
+    generator: LTCGenerator,
+    // The last incoming timecode and user bits
+    latched: Option<(SMPTETimecode, u32)>,
+    jammed: bool,
+    // Stream position of the next sample written by `fill`
+    position: i64,
+}
+
+impl JamSync {
+    pub fn try_new(config: &JamSyncConfig) -> Result<Self, LTCEncoderError> {
+        let chaser = Chaser::new(&ChaserConfig {
+            sample_rate: config.generator.sample_rate,
+            rate: config.generator.rate,
+            flags: config.generator.flags,
+            latency: config.latency,
+            lock_frames: config.lock_frames,
+            ..Default::default()
+        });
+        Ok(JamSync {
+            config: *config,
+            chaser,
+            generator: LTCGenerator::try_new(&config.generator, &SMPTETimecode::default())?,
+            latched: None,
+            jammed: false,
+            position: 0,
+        })
+    }
+
+    pub fn is_jammed(&self) -> bool {
+        self.jammed
+    }
+
+    pub fn generator(&self) -> &LTCGenerator {
+        &self.generator
+    }
+
+    pub fn set_position(&mut self, sample: i64) {
+        self.position = sample;
+    }
+
+    // Forgets the jam, the output goes silent until the next one
+    pub fn reset(&mut self) {
+        self.chaser.reset();
+        self.latched = None;
+        self.jammed = false;
+    }
+
+    // Returns true when the frame caused a (re-)jam
+    pub fn push(&mut self, frame: &LTCFrameExt) -> Result<bool, LTCEncoderError> {
+        self.chaser.push(frame);
+        if self.jammed && self.config.mode == JamMode::OneShot {
+            return Ok(false);
+        }
+
+        let user_bits = frame.ltc().get_user_bits();
+        self.latched = Some((
+            frame.ltc().to_timecode(self.config.generator.flags),
+            user_bits,
+        ));
+        if self.jammed {
+            self.generator.set_user_bits(user_bits);
+        }
+
+        if self.chaser.state() != LockState::Locked {
+            return Ok(false);
+        }
+        let Some(target) = self.chaser.position_at(self.position as f64) else {
+            return Ok(false);
+        };
+        if self.jammed {
+            let day = self.config.generator.rate.frames_per_day() as f64;
+            let mut drift = target - self.generator.position();
+            drift -= (drift / day).round() * day;
+            let samples_per_frame = self
+                .config
+                .generator
+                .rate
+                .samples_per_frame(self.config.generator.sample_rate);
+            if drift.abs() * samples_per_frame <= self.config.tolerance {
+                return Ok(false);
+            }
+        }
+        self.jam(target)?;
+        Ok(true)
+    }
+
+    pub fn fill<S: Sample>(&mut self, out: &mut [S]) -> Result<(), LTCEncoderError> {
+        if self.jammed {
+            self.generator.fill(out)?;
+        } else {
+            out.fill(S::from_ltc(128));
+        }
+        self.position += out.len() as i64;
+        Ok(())
+    }
+
+    // Starts the generator so that it is at `target` frames at the current output position
+    fn jam(&mut self, target: f64) -> Result<(), LTCEncoderError> {
+        let Some((timecode, user_bits)) = &self.latched else {
+            return Ok(());
+        };
+        let rate = self.config.generator.rate;
+        let frames = target.floor();
+        self.generator
+            .set_timecode(&timecode.with_frames(frames as i64, rate));
+        self.generator.set_user_bits(*user_bits);
+        let samples_per_frame = rate.samples_per_frame(self.config.generator.sample_rate);
+        self.generator
+            .skip_samples(((target - frames) * samples_per_frame).round() as usize)?;
+        self.jammed = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::consts::LtcBgFlags;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::rate::FrameRate;
+    use crate::api::Timezone;
+
+    fn timecode(hours: u8, frame: u8) -> SMPTETimecode {
+        SMPTETimecode::new(Timezone::default(), 0, 0, 0, hours, 0, 0, frame)
+    }
+
+    fn ltc(start: &SMPTETimecode, frames: usize) -> Vec<u8> {
+        let mut generator = LTCGenerator::try_new(&LTCGeneratorConfig::default(), start).unwrap();
+        let mut audio = vec![0; frames * 1920];
+        generator.fill(&mut audio).unwrap();
+        audio
+    }
+
+    // Runs the jam sync over the input and returns its output
+    fn jam(jam: &mut JamSync, input: &[u8]) -> Vec<u8> {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut output = vec![0; input.len()];
+        for (i, (block, out)) in input.chunks(480).zip(output.chunks_mut(480)).enumerate() {
+            decoder.write(block, (i * 480) as i64);
+            while let Some(frame) = decoder.read() {
+                jam.push(&frame).unwrap();
+            }
+            jam.fill(out).unwrap();
+        }
+        output
+    }
+
+    // (frames since midnight, off_end) of every frame decoded from the audio
+    fn decode(audio: &[u8]) -> Vec<(i64, i64)> {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut frames = Vec::new();
+        for (i, block) in audio.chunks(1920).enumerate() {
+            decoder.write(block, (i * 1920) as i64);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                frames.push((timecode.to_frames(FrameRate::FPS_25), frame.off_end()));
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn test_jam_sync_phase() {
+        let start = timecode(10, 0);
+        let mut input = ltc(&start, 30);
+        // The cable is pulled after 30 frames, the generator has to keep going
+        input.extend(std::iter::repeat_n(128, 30 * 1920));
+        let mut jam_sync = JamSync::try_new(&JamSyncConfig::default()).unwrap();
+        let output = jam(&mut jam_sync, &input);
+        assert!(jam_sync.is_jammed());
+
+        // Every frame lines up with where the incoming LTC has (or would have) put it
+        let start = start.to_frames(FrameRate::FPS_25);
+        let (first, first_end) = decode(&input)[0];
+        let output = decode(&output);
+        assert!(output.len() > 40);
+        for (frames, end) in output {
+            let expected = first_end + (frames - first) * 1920;
+            assert!(
+                (end - expected).abs() <= 1,
+                "{} {end} {expected}",
+                frames - start
+            );
+        }
+    }
+
+    #[test]
+    fn test_jam_sync_modes() {
+        let mut input = ltc(&timecode(10, 0), 30);
+        input.extend(ltc(&timecode(11, 0), 30));
+
+        let mut one_shot = JamSync::try_new(&JamSyncConfig::default()).unwrap();
+        let output = decode(&jam(&mut one_shot, &input));
+        let (last, _) = output.last().unwrap();
+        assert_eq!(
+            SMPTETimecode::from_frames(*last, FrameRate::FPS_25).hours(),
+            10
+        );
+
+        let mut continuous = JamSync::try_new(&JamSyncConfig {
+            mode: JamMode::Continuous,
+            ..Default::default()
+        })
+        .unwrap();
+        let output = decode(&jam(&mut continuous, &input));
+        let (last, _) = output.last().unwrap();
+        assert_eq!(
+            SMPTETimecode::from_frames(*last, FrameRate::FPS_25).hours(),
+            11
+        );
+    }
+}
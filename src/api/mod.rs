@@ -1,14 +1,40 @@
+#[cfg(feature = "artnet")]
+pub mod artnet;
+pub mod chase;
 pub mod consts;
+pub mod continuity;
+pub mod convert;
 pub mod decoder;
 pub mod encoder;
+pub mod film;
 pub mod frame;
+pub mod generator;
+pub mod hfr;
+pub mod jam;
+pub mod mtc;
+#[cfg(feature = "osc")]
+pub mod osc;
+pub mod range;
+pub mod rate;
+pub mod sample;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod shuttle;
+pub mod speed;
+pub mod stamp;
+pub mod track;
 
 use std::fmt::Display;
+use std::str::FromStr;
 
 use crate::error;
-use crate::error::TimecodeError;
 use crate::raw;
-pub use error::{LTCDecoderError, LTCEncoderError};
+#[cfg(feature = "artnet")]
+pub use error::ArtNetError;
+#[cfg(feature = "osc")]
+pub use error::OSCError;
+pub use error::{LTCDecoderError, LTCEncoderError, MTCError, TimecodeError};
+use rate::FrameRate;
 
 #[derive(Debug)]
 pub struct SMPTETimecode {
@@ -62,6 +88,16 @@ impl Default for SMPTETimecode {
     }
 }
 
+impl Clone for SMPTETimecode {
+    fn clone(&self) -> Self {
+        // SAFETY: the pointer is not null, the copy is moved into its own Box
+        let inner = Box::new(unsafe { *self.inner_unsafe_ptr });
+        SMPTETimecode {
+            inner_unsafe_ptr: Box::into_raw(inner),
+        }
+    }
+}
+
 impl Drop for SMPTETimecode {
     fn drop(&mut self) {
         if !self.inner_unsafe_ptr.is_null() {
@@ -126,6 +162,74 @@ impl SMPTETimecode {
     pub fn frame(&self) -> u8 {
         unsafe { (*self.inner_unsafe_ptr).frame }
     }
+
+    // Builds a time of day timecode (no date) from the number of frames since midnight
+    pub fn from_frames(frames: i64, rate: FrameRate) -> Self {
+        let (hours, minutes, seconds, frame) = rate.frames_to_hmsf(frames);
+        SMPTETimecode::new(Timezone::default(), 0, 0, 0, hours, minutes, seconds, frame)
+    }
+
+    // Same date and timezone, with the time of day moved to `frames` since midnight
+    pub fn with_frames(&self, frames: i64, rate: FrameRate) -> Self {
+        let (hours, minutes, seconds, frame) = rate.frames_to_hmsf(frames);
+        SMPTETimecode::new(
+            self.timezone(),
+            self.years(),
+            self.months(),
+            self.days(),
+            hours,
+            minutes,
+            seconds,
+            frame,
+        )
+    }
+
+    // HH:MM:SS:FF, with ; before the frames for drop frame
+    pub fn to_time_string(&self, drop_frame: bool) -> String {
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours(),
+            self.minutes(),
+            self.seconds(),
+            if drop_frame { ';' } else { ':' },
+            self.frame()
+        )
+    }
+
+    // Number of frames since midnight, the date is ignored
+    pub fn to_frames(&self, rate: FrameRate) -> i64 {
+        rate.hmsf_to_frames(self.hours(), self.minutes(), self.seconds(), self.frame())
+    }
+}
+
+// Parses HH:MM:SS:FF, with ; or . before the frames for drop frame. The date is left empty.
+impl FromStr for SMPTETimecode {
+    type Err = TimecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s
+            .trim()
+            .split([':', ';', '.'])
+            .map(|field| field.parse::<u8>());
+        let mut next = |limit: u8| match fields.next() {
+            Some(Ok(value)) if value < limit => Ok(value),
+            _ => Err(TimecodeError::ParseTimecode),
+        };
+        let (hours, minutes, seconds, frame) = (next(24)?, next(60)?, next(60)?, next(60)?);
+        if fields.next().is_some() {
+            return Err(TimecodeError::ParseTimecode);
+        }
+        Ok(SMPTETimecode::new(
+            Timezone::default(),
+            0,
+            0,
+            0,
+            hours,
+            minutes,
+            seconds,
+            frame,
+        ))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -170,6 +274,24 @@ impl From<[u8; 6]> for Timezone {
     }
 }
 
+// Parses +HHMM or -HHMM
+impl FromStr for Timezone {
+    type Err = TimecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.trim().as_bytes();
+        let valid = bytes.len() == 5
+            && (bytes[0] == b'+' || bytes[0] == b'-')
+            && bytes[1..].iter().all(u8::is_ascii_digit);
+        if !valid {
+            return Err(TimecodeError::ParseTimezone);
+        }
+        let mut timezone = [0u8; 6];
+        timezone[..5].copy_from_slice(bytes);
+        Ok(timezone.into())
+    }
+}
+
 impl Timezone {
     pub fn new(timezone: [i8; 6]) -> Self {
         Timezone(timezone)
@@ -183,6 +305,7 @@ impl Timezone {
 #[allow(non_camel_case_types)]
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LTCTVStandard {
     #[default]
     LTCTV_525_60 = 0, // 30fps
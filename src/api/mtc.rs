@@ -0,0 +1,599 @@
+// MIDI Timecode. Quarter frame messages spread one timecode over two frames, four per frame,
+// full frame SysEx messages carry the whole timecode at once and are sent when locating.
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::stamp::{FrameStamper, FrameStamperConfig};
+use super::{SMPTETimecode, Timezone};
+use crate::error::MTCError;
+
+pub const MTC_QUARTER_FRAME: u8 = 0xF1;
+pub const MTC_FULL_FRAME_LEN: usize = 10;
+
+// The rate goes into the two bits above the hours
+fn rate_code(rate: FrameRate) -> Result<u8, MTCError> {
+    rate.smpte_type().ok_or(MTCError::UnsupportedRate)
+}
+
+// Quarter frame `piece` (0 to 7) of `timecode`, as the status byte and the data byte
+pub fn quarter_frame(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+    piece: u8,
+) -> Result<[u8; 2], MTCError> {
+    let value = match piece {
+        0 => timecode.frame() & 0x0F,
+        1 => timecode.frame() >> 4 & 0x01,
+        2 => timecode.seconds() & 0x0F,
+        3 => timecode.seconds() >> 4 & 0x03,
+        4 => timecode.minutes() & 0x0F,
+        5 => timecode.minutes() >> 4 & 0x03,
+        6 => timecode.hours() & 0x0F,
+        7 => rate_code(rate)? << 1 | (timecode.hours() >> 4 & 0x01),
+        _ => return Err(MTCError::InvalidMessage),
+    };
+    Ok([MTC_QUARTER_FRAME, piece << 4 | value])
+}
+
+// The four quarter frames to send, evenly spaced, while `timecode` is playing. A run of eight
+// pieces starts on every even frame count, so going forward an even frame sends pieces 0 to 3
+// of its own timecode and the odd frame after it 4 to 7 of the same one. Going backwards the
+// odd frame comes first and sends 7 to 4, the even frame 3 to 0.
+pub fn quarter_frames(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+    reverse: bool,
+) -> Result<[[u8; 2]; 4], MTCError> {
+    rate_code(rate)?;
+    let frames = timecode.to_frames(rate);
+    let odd = frames.rem_euclid(2) == 1;
+    let base = if odd {
+        timecode.with_frames(frames - 1, rate)
+    } else {
+        timecode.clone()
+    };
+    let first = if odd { 4 } else { 0 };
+
+    let mut messages = [[0u8; 2]; 4];
+    for (i, message) in messages.iter_mut().enumerate() {
+        let piece = if reverse {
+            first + 3 - i as u8
+        } else {
+            first + i as u8
+        };
+        *message = quarter_frame(&base, rate, piece)?;
+    }
+    Ok(messages)
+}
+
+// Full frame SysEx to the whole system (device id 0x7F)
+pub fn full_frame(
+    timecode: &SMPTETimecode,
+    rate: FrameRate,
+) -> Result<[u8; MTC_FULL_FRAME_LEN], MTCError> {
+    Ok([
+        0xF0,
+        0x7F,
+        0x7F,
+        0x01,
+        0x01,
+        rate_code(rate)? << 5 | (timecode.hours() & 0x1F),
+        timecode.minutes(),
+        timecode.seconds(),
+        timecode.frame(),
+        0xF7,
+    ])
+}
+
+pub fn quarter_frames_from_ltc(
+    frame: &LTCFrameExt,
+    rate: FrameRate,
+) -> Result<[[u8; 2]; 4], MTCError> {
+    let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+    quarter_frames(&timecode, rate, frame.reverse())
+}
+
+pub fn full_frame_from_ltc(
+    frame: &LTCFrameExt,
+    rate: FrameRate,
+) -> Result<[u8; MTC_FULL_FRAME_LEN], MTCError> {
+    full_frame(&frame.ltc().to_timecode(LtcBgFlags::default()), rate)
+}
+
+fn timecode(hours: u8, minutes: u8, seconds: u8, frame: u8) -> Result<SMPTETimecode, MTCError> {
+    if hours > 23 || minutes > 59 || seconds > 59 || frame > 29 {
+        return Err(MTCError::InvalidMessage);
+    }
+    Ok(SMPTETimecode::new(
+        Timezone::default(),
+        0,
+        0,
+        0,
+        hours,
+        minutes,
+        seconds,
+        frame,
+    ))
+}
+
+// Parses a full frame SysEx from any device id
+pub fn parse_full_frame(message: &[u8]) -> Result<(SMPTETimecode, FrameRate), MTCError> {
+    match message {
+        [0xF0, 0x7F, _, 0x01, 0x01, hours, minutes, seconds, frame, 0xF7] => Ok((
+            timecode(hours & 0x1F, *minutes, *seconds, *frame)?,
+            FrameRate::from_smpte_type(hours >> 5),
+        )),
+        _ => Err(MTCError::InvalidMessage),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MTCTimecode {
+    // The frame playing when the message completing the timecode arrived
+    pub timecode: SMPTETimecode,
+    pub rate: FrameRate,
+    pub reverse: bool,
+    // Whether it came from a full frame message, i.e. the sender located instead of playing
+    pub full_frame: bool,
+}
+
+// Reassembles quarter frames and full frame messages into timecode
+#[derive(Debug, Default)]
+pub struct MTCDecoder {
+    pieces: [u8; 8],
+    // Bit n is set once piece n of the current run arrived
+    received: u8,
+    last: Option<u8>,
+    reverse: bool,
+}
+
+impl MTCDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // Takes one complete MIDI message. Returns the timecode once a run of eight quarter frames
+    // or a full frame message is complete, messages that aren't MTC are ignored.
+    pub fn push(&mut self, message: &[u8]) -> Result<Option<MTCTimecode>, MTCError> {
+        match message {
+            [MTC_QUARTER_FRAME, data] => self.push_quarter_frame(*data),
+            [0xF0, 0x7F, _, 0x01, 0x01, ..] => {
+                let (timecode, rate) = parse_full_frame(message)?;
+                self.reset();
+                Ok(Some(MTCTimecode {
+                    timecode,
+                    rate,
+                    reverse: false,
+                    full_frame: true,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn push_quarter_frame(&mut self, data: u8) -> Result<Option<MTCTimecode>, MTCError> {
+        if data & 0x80 != 0 {
+            return Err(MTCError::InvalidMessage);
+        }
+        let piece = data >> 4;
+        match self.last {
+            // On a change of direction only the last piece still belongs to the run
+            Some(last) if piece == (last + 1) % 8 => {
+                if self.reverse {
+                    self.received &= 1 << last;
+                }
+                self.reverse = false;
+            }
+            Some(last) if piece == (last + 7) % 8 => {
+                if !self.reverse {
+                    self.received &= 1 << last;
+                }
+                self.reverse = true;
+            }
+            // A gap, start over
+            _ => self.received = 0,
+        }
+        self.last = Some(piece);
+        self.pieces[piece as usize] = data & 0x0F;
+        self.received |= 1 << piece;
+
+        let end = if self.reverse { 0 } else { 7 };
+        if self.received != 0xFF || piece != end {
+            return Ok(None);
+        }
+        self.received = 0;
+
+        let p = self.pieces;
+        let rate = FrameRate::from_smpte_type(p[7] >> 1);
+        let base = timecode(
+            p[6] | (p[7] & 0x01) << 4,
+            p[4] | p[5] << 4,
+            p[2] | p[3] << 4,
+            p[0] | p[1] << 4,
+        )?;
+        // Going forward the run ends a frame after the one it carries
+        let timecode = if self.reverse {
+            base
+        } else {
+            base.with_frames(base.to_frames(rate) + 1, rate)
+        };
+        Ok(Some(MTCTimecode {
+            timecode,
+            rate,
+            reverse: self.reverse,
+            full_frame: false,
+        }))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MTCMessage {
+    QuarterFrame([u8; 2]),
+    FullFrame([u8; MTC_FULL_FRAME_LEN]),
+}
+
+impl MTCMessage {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            MTCMessage::QuarterFrame(bytes) => bytes,
+            MTCMessage::FullFrame(bytes) => bytes,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimedMTCMessage {
+    // Stream position the message belongs at
+    pub sample: i64,
+    // The same position in nanoseconds from sample 0
+    pub nanos: i64,
+    pub message: MTCMessage,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MTCBridgeConfig {
+    pub stamper: FrameStamperConfig,
+    // Frames quarter frames keep coming after the last decoded LTC frame before the bridge
+    // stops, so that a few damaged frames don't interrupt the MTC
+    pub freewheel: u32,
+}
+
+impl Default for MTCBridgeConfig {
+    fn default() -> Self {
+        MTCBridgeConfig {
+            stamper: FrameStamperConfig::default(),
+            freewheel: 4,
+        }
+    }
+}
+
+// Turns decoded LTC into MTC with every quarter frame placed in the stream, so that the MIDI
+// output keeps the phase of the LTC frame boundaries. Quarter frames follow the frame boundary
+// timeline of a `FrameStamper`. The LTC of a frame is only known once it ended, so quarter
+// frames are predicted from the frames before: the output runs ahead of the decoded LTC and
+// behind the stream position by the time until the next `poll`. Only the latest locate is
+// sent: when the bridge relocks twice between polls the first full frame is dropped, as its
+// quarter frames never went out.
+#[derive(Debug)]
+pub struct MTCBridge {
+    config: MTCBridgeConfig,
+    stamper: FrameStamper,
+    // The next quarter frame, in quarters of a frame since midnight
+    next: Option<i64>,
+    reverse: bool,
+    // Frames since midnight of the last decoded frame, without wrapping
+    last: i64,
+    full_frame: Option<TimedMTCMessage>,
+}
+
+impl MTCBridge {
+    pub fn try_new(config: &MTCBridgeConfig) -> Result<Self, MTCError> {
+        rate_code(config.stamper.rate)?;
+        Ok(MTCBridge {
+            config: *config,
+            stamper: FrameStamper::new(&config.stamper),
+            next: None,
+            reverse: false,
+            last: 0,
+            full_frame: None,
+        })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.next.is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.stamper.reset();
+        self.next = None;
+        self.full_frame = None;
+    }
+
+    pub fn push(&mut self, frame: LTCFrameExt) {
+        let reverse = frame.reverse();
+        if reverse != self.reverse {
+            self.stamper.reset();
+        }
+        let stamped = self.stamper.push(frame);
+        let Some(position) = self.stamper.position_at(stamped.start) else {
+            return;
+        };
+        let frames = position.round() as i64;
+        self.last = frames;
+
+        // Going forward the frame after this one starts at its end, going backwards the one
+        // before it ends where this one started
+        let expected = 4 * if reverse { frames } else { frames + 1 };
+        let continuous =
+            reverse == self.reverse && self.next.is_some_and(|next| (next - expected).abs() <= 4);
+        self.reverse = reverse;
+        if continuous {
+            return;
+        }
+
+        // Locate with a full frame message, then start over with the quarter frames
+        let rate = self.config.stamper.rate;
+        let playing = if reverse { frames - 1 } else { frames + 1 };
+        let timecode = SMPTETimecode::from_frames(playing, rate);
+        let sample = self.sample_at(expected);
+        self.full_frame = full_frame(&timecode, rate)
+            .ok()
+            .map(|bytes| TimedMTCMessage {
+                sample,
+                nanos: self.nanos(sample),
+                message: MTCMessage::FullFrame(bytes),
+            });
+        self.next = Some(expected);
+    }
+
+    // The messages that belong before stream position `until`
+    pub fn poll(&mut self, until: i64) -> Vec<TimedMTCMessage> {
+        let mut messages: Vec<_> = self.full_frame.take().into_iter().collect();
+        let rate = self.config.stamper.rate;
+        while let Some(quarter) = self.next {
+            let sample = self.sample_at(quarter);
+            if sample >= until {
+                break;
+            }
+            // The frame that starts playing at this quarter, and which of its quarters it is
+            let (frames, index) = if self.reverse {
+                let frames = (quarter + 3).div_euclid(4) - 1;
+                (frames, 4 * (frames + 1) - quarter)
+            } else {
+                (quarter.div_euclid(4), quarter.rem_euclid(4))
+            };
+            if (frames - self.last).abs() > self.config.freewheel as i64 {
+                self.next = None;
+                break;
+            }
+            let timecode = SMPTETimecode::from_frames(frames, rate);
+            if let Ok(quarters) = quarter_frames(&timecode, rate, self.reverse) {
+                messages.push(TimedMTCMessage {
+                    sample,
+                    nanos: self.nanos(sample),
+                    message: MTCMessage::QuarterFrame(quarters[index as usize]),
+                });
+            }
+            self.next = Some(if self.reverse {
+                quarter - 1
+            } else {
+                quarter + 1
+            });
+        }
+        messages
+    }
+
+    fn sample_at(&self, quarter: i64) -> i64 {
+        self.stamper
+            .sample_at(quarter as f64 / 4.0)
+            .map_or(0, |sample| sample.round() as i64)
+    }
+
+    fn nanos(&self, sample: i64) -> i64 {
+        (sample as f64 * 1e9 / self.config.stamper.sample_rate).round() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::consts::SampleType;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::shuttle::{ShuttleConfig, ShuttleGenerator};
+
+    fn shuttle(speed: f64, duration: f64) -> Vec<SampleType> {
+        let start: SMPTETimecode = "01:00:00:00".parse().unwrap();
+        let mut shuttle = ShuttleGenerator::try_new(&ShuttleConfig::default(), &start).unwrap();
+        shuttle.render(duration, |_| speed).unwrap()
+    }
+
+    // Runs the audio through a decoder and the bridge, returns the messages and the
+    // (first sample, last sample, frames since midnight) of every decoded frame
+    fn bridge(audio: &[SampleType]) -> (Vec<TimedMTCMessage>, Vec<(i64, i64, i64)>) {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut bridge = MTCBridge::try_new(&MTCBridgeConfig::default()).unwrap();
+        let mut messages = Vec::new();
+        let mut frames = Vec::new();
+        for (i, block) in audio.chunks(1024).enumerate() {
+            decoder.write(block, (i * 1024) as i64);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                frames.push((
+                    frame.off_start(),
+                    frame.off_end(),
+                    timecode.to_frames(FrameRate::FPS_25),
+                ));
+                bridge.push(frame);
+            }
+            messages.extend(bridge.poll((i * 1024 + block.len()) as i64));
+        }
+        (messages, frames)
+    }
+
+    // Checks every timecode the MTC decoder gets against the LTC frame playing at that sample
+    fn assert_in_phase(messages: &[TimedMTCMessage], frames: &[(i64, i64, i64)], reverse: bool) {
+        let mut decoder = MTCDecoder::new();
+        let mut decoded = 0;
+        for message in messages {
+            let Some(mtc) = decoder.push(message.message.as_bytes()).unwrap() else {
+                continue;
+            };
+            let playing = frames
+                .iter()
+                .find(|(start, end, _)| (*start..=*end).contains(&message.sample));
+            if let (false, Some((_, _, label))) = (mtc.full_frame, playing) {
+                assert_eq!(mtc.reverse, reverse);
+                assert_eq!(mtc.timecode.to_frames(FrameRate::FPS_25), *label);
+                decoded += 1;
+            }
+        }
+        assert!(decoded > 10, "{decoded}");
+    }
+
+    #[test]
+    fn test_full_frame() {
+        let timecode: SMPTETimecode = "23:59:58:29".parse().unwrap();
+        let message = full_frame(&timecode, FrameRate::FPS_29_97_DF).unwrap();
+        assert_eq!(
+            message,
+            [0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x57, 59, 58, 29, 0xF7]
+        );
+        let (parsed, rate) = parse_full_frame(&message).unwrap();
+        assert_eq!(parsed.to_time_string(false), "23:59:58:29");
+        assert_eq!(rate, FrameRate::FPS_29_97_DF);
+
+        assert_eq!(
+            full_frame(&timecode, FrameRate::new(50, 1, false)),
+            Err(MTCError::UnsupportedRate)
+        );
+        assert!(parse_full_frame(&message[..9]).is_err());
+    }
+
+    #[test]
+    fn test_quarter_frames() {
+        let rate = FrameRate::FPS_25;
+        let timecode: SMPTETimecode = "17:42:30:18".parse().unwrap();
+        let messages: Vec<_> = (0..8)
+            .map(|piece| quarter_frame(&timecode, rate, piece).unwrap()[1])
+            .collect();
+        assert_eq!(messages, [0x02, 0x11, 0x2E, 0x31, 0x4A, 0x52, 0x61, 0x73]);
+
+        // Frame 18 sends pieces 0 to 3, frame 19 pieces 4 to 7 of 18
+        let next = timecode.with_frames(timecode.to_frames(rate) + 1, rate);
+        let odd = quarter_frames(&next, rate, false).unwrap();
+        assert_eq!(odd[0], quarter_frame(&timecode, rate, 4).unwrap());
+        let reverse = quarter_frames(&next, rate, true).unwrap();
+        assert_eq!(reverse[0], quarter_frame(&timecode, rate, 7).unwrap());
+    }
+
+    #[test]
+    fn test_decoder() {
+        for rate in [
+            FrameRate::FPS_24,
+            FrameRate::FPS_25,
+            FrameRate::FPS_29_97_DF,
+            FrameRate::FPS_30,
+        ] {
+            for reverse in [false, true] {
+                let mut decoder = MTCDecoder::new();
+                // Runs start on even frames, backwards that's the odd frame before them
+                let start = 10 * 60 * rate.timebase() as i64 - 4 + reverse as i64;
+                let mut decoded = Vec::new();
+                for i in 0..12 {
+                    let frames = if reverse { start - i } else { start + i };
+                    let timecode = SMPTETimecode::from_frames(frames, rate);
+                    for message in quarter_frames(&timecode, rate, reverse).unwrap() {
+                        if let Some(mtc) = decoder.push(&message).unwrap() {
+                            assert_eq!(mtc.rate, rate);
+                            assert_eq!(mtc.reverse, reverse);
+                            assert_eq!(mtc.timecode.to_frames(rate), frames);
+                            decoded.push(frames);
+                        }
+                    }
+                }
+                assert_eq!(decoded.len(), 6);
+            }
+        }
+
+        let mut decoder = MTCDecoder::new();
+        assert!(decoder.push(&[0x90, 0x40, 0x7F]).unwrap().is_none());
+        let timecode: SMPTETimecode = "01:00:00:00".parse().unwrap();
+        let mtc = decoder
+            .push(&full_frame(&timecode, FrameRate::FPS_24).unwrap())
+            .unwrap()
+            .unwrap();
+        assert!(mtc.full_frame);
+        assert_eq!(mtc.timecode.to_time_string(false), "01:00:00:00");
+    }
+
+    #[test]
+    fn test_bridge() {
+        let (messages, frames) = bridge(&shuttle(1.0, 2.0));
+        assert!(matches!(messages[0].message, MTCMessage::FullFrame(_)));
+        for pair in messages[1..].windows(2) {
+            let spacing = pair[1].sample - pair[0].sample;
+            assert!((478..=482).contains(&spacing), "{spacing}");
+            let nanos = pair[1].nanos - pair[0].nanos;
+            assert!((nanos - spacing * 1_000_000 / 48).abs() <= 1, "{nanos}");
+        }
+        assert_in_phase(&messages, &frames, false);
+
+        let (messages, frames) = bridge(&shuttle(-1.0, 2.0));
+        assert_in_phase(&messages, &frames, true);
+    }
+
+    #[test]
+    fn test_bridge_dropout() {
+        let mut audio = shuttle(1.0, 3.0);
+        audio[48_000..72_000].fill(128);
+        let (messages, frames) = bridge(&audio);
+
+        // Quarter frames stop a few frames into the dropout and resume with a full frame
+        let last = messages
+            .iter()
+            .rfind(|message| message.sample < 72_000)
+            .unwrap();
+        assert!(last.sample < 48_000 + 6 * 1920, "{}", last.sample);
+        let full_frames: Vec<_> = messages
+            .iter()
+            .filter(|message| matches!(message.message, MTCMessage::FullFrame(_)))
+            .collect();
+        assert_eq!(full_frames.len(), 2);
+        assert!(full_frames[1].sample > 72_000);
+        assert_in_phase(&messages, &frames, false);
+    }
+
+    #[test]
+    fn test_bridge_relock() {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&shuttle(1.0, 2.0), 0);
+        let mut frames = std::iter::from_fn(|| decoder.read());
+        let first = frames.next().unwrap();
+        let jump = frames.nth(19).unwrap();
+        let label = jump
+            .ltc()
+            .to_timecode(LtcBgFlags::default())
+            .to_frames(FrameRate::FPS_25);
+        let until = jump.off_end() + 1920;
+
+        // Two locates before the next poll only send the second one
+        let mut bridge = MTCBridge::try_new(&MTCBridgeConfig::default()).unwrap();
+        bridge.push(first);
+        bridge.push(jump);
+        let full_frames: Vec<_> = bridge
+            .poll(until)
+            .into_iter()
+            .filter_map(|message| match message.message {
+                MTCMessage::FullFrame(bytes) => Some(bytes),
+                MTCMessage::QuarterFrame(_) => None,
+            })
+            .collect();
+        assert_eq!(full_frames.len(), 1);
+        let (timecode, _) = parse_full_frame(&full_frames[0]).unwrap();
+        assert_eq!(timecode.to_frames(FrameRate::FPS_25), label + 1);
+    }
+}
@@ -0,0 +1,260 @@
+// OSC output over UDP, enabled with the `osc` feature
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+
+use super::chase::LockState;
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+use crate::error::OSCError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OSCArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+// OSC strings are null terminated and padded to a multiple of four bytes
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+}
+
+pub fn osc_message(address: &str, arguments: &[OSCArgument]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_string(&mut out, address);
+    let tags: String = std::iter::once(',')
+        .chain(arguments.iter().map(|argument| match argument {
+            OSCArgument::Int(_) => 'i',
+            OSCArgument::Float(_) => 'f',
+            OSCArgument::String(_) => 's',
+        }))
+        .collect();
+    write_string(&mut out, &tags);
+    for argument in arguments {
+        match argument {
+            OSCArgument::Int(value) => out.extend_from_slice(&value.to_be_bytes()),
+            OSCArgument::Float(value) => out.extend_from_slice(&value.to_be_bytes()),
+            OSCArgument::String(value) => write_string(&mut out, value),
+        }
+    }
+    out
+}
+
+fn read_string<'a>(packet: &'a [u8], offset: &mut usize) -> Result<&'a str, OSCError> {
+    let rest = packet.get(*offset..).ok_or(OSCError::InvalidMessage)?;
+    let len = rest
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or(OSCError::InvalidMessage)?;
+    let s = std::str::from_utf8(&rest[..len]).map_err(|_| OSCError::InvalidMessage)?;
+    *offset += (len + 4) & !3;
+    Ok(s)
+}
+
+fn read_word(packet: &[u8], offset: &mut usize) -> Result<[u8; 4], OSCError> {
+    let word = packet
+        .get(*offset..*offset + 4)
+        .ok_or(OSCError::InvalidMessage)?;
+    *offset += 4;
+    Ok([word[0], word[1], word[2], word[3]])
+}
+
+// Parses a single message with int, float and string arguments, bundles aren't supported
+pub fn parse_osc_message(packet: &[u8]) -> Result<(String, Vec<OSCArgument>), OSCError> {
+    let mut offset = 0;
+    let address = read_string(packet, &mut offset)?;
+    if !address.starts_with('/') {
+        return Err(OSCError::InvalidMessage);
+    }
+    let tags = read_string(packet, &mut offset)?;
+    let tags = tags.strip_prefix(',').ok_or(OSCError::InvalidMessage)?;
+    let mut arguments = Vec::with_capacity(tags.len());
+    for tag in tags.chars() {
+        arguments.push(match tag {
+            'i' => OSCArgument::Int(i32::from_be_bytes(read_word(packet, &mut offset)?)),
+            'f' => OSCArgument::Float(f32::from_be_bytes(read_word(packet, &mut offset)?)),
+            's' => OSCArgument::String(read_string(packet, &mut offset)?.to_owned()),
+            _ => return Err(OSCError::InvalidMessage),
+        });
+    }
+    Ok((address.to_owned(), arguments))
+}
+
+fn lock_state_name(state: LockState) -> &'static str {
+    match state {
+        LockState::Unlocked => "unlocked",
+        LockState::Locking => "locking",
+        LockState::Locked => "locked",
+        LockState::Freewheel => "freewheel",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OSCSenderConfig {
+    pub bind: SocketAddr,
+    pub target: SocketAddr,
+    // Used to write timecode with ; for drop frame
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // An empty address leaves that message out
+    pub timecode_address: String,
+    pub user_bits_address: String,
+    pub lock_address: String,
+    pub speed_address: String,
+}
+
+impl Default for OSCSenderConfig {
+    fn default() -> Self {
+        OSCSenderConfig {
+            bind: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into(),
+            target: SocketAddrV4::new(Ipv4Addr::LOCALHOST, 53000).into(),
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            timecode_address: "/ltc/timecode".to_owned(),
+            user_bits_address: "/ltc/userbits".to_owned(),
+            lock_address: "/ltc/lock".to_owned(),
+            speed_address: "/ltc/speed".to_owned(),
+        }
+    }
+}
+
+// Sends timecode as "HH:MM:SS:FF", user bits as an int, the lock state as one of "unlocked",
+// "locking", "locked" and "freewheel" and the speed as a float
+#[derive(Debug)]
+pub struct OSCSender {
+    config: OSCSenderConfig,
+    socket: UdpSocket,
+}
+
+impl OSCSender {
+    pub fn try_new(config: &OSCSenderConfig) -> Result<Self, OSCError> {
+        Ok(OSCSender {
+            config: config.clone(),
+            socket: UdpSocket::bind(config.bind)?,
+        })
+    }
+
+    fn send(&self, address: &str, argument: OSCArgument) -> Result<(), OSCError> {
+        if !address.is_empty() {
+            self.socket
+                .send_to(&osc_message(address, &[argument]), self.config.target)?;
+        }
+        Ok(())
+    }
+
+    pub fn send_timecode(&self, timecode: &SMPTETimecode) -> Result<(), OSCError> {
+        let time = timecode.to_time_string(self.config.rate.is_drop_frame());
+        self.send(&self.config.timecode_address, OSCArgument::String(time))
+    }
+
+    pub fn send_user_bits(&self, user_bits: u32) -> Result<(), OSCError> {
+        self.send(
+            &self.config.user_bits_address,
+            OSCArgument::Int(user_bits as i32),
+        )
+    }
+
+    pub fn send_lock_state(&self, state: LockState) -> Result<(), OSCError> {
+        self.send(
+            &self.config.lock_address,
+            OSCArgument::String(lock_state_name(state).to_owned()),
+        )
+    }
+
+    pub fn send_speed(&self, speed: f64) -> Result<(), OSCError> {
+        self.send(&self.config.speed_address, OSCArgument::Float(speed as f32))
+    }
+
+    // Timecode and user bits of a decoded frame, call it for every frame `LTCDecoder::read`
+    // returns
+    pub fn send_frame(&self, frame: &LTCFrameExt) -> Result<(), OSCError> {
+        let ltc = frame.ltc();
+        self.send_timecode(&ltc.to_timecode(self.config.flags))?;
+        self.send_user_bits(ltc.get_user_bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_osc_message() {
+        let message = osc_message(
+            "/ltc/timecode",
+            &[
+                OSCArgument::String("01:00:00:00".to_owned()),
+                OSCArgument::Int(-2),
+                OSCArgument::Float(1.5),
+            ],
+        );
+        assert_eq!(&message[..16], b"/ltc/timecode\0\0\0");
+        assert_eq!(&message[16..24], b",sif\0\0\0\0");
+        assert_eq!(&message[24..36], b"01:00:00:00\0");
+        assert_eq!(&message[36..40], &[0xFF, 0xFF, 0xFF, 0xFE]);
+        assert_eq!(&message[40..], &[0x3F, 0xC0, 0x00, 0x00]);
+
+        let (address, arguments) = parse_osc_message(&message).unwrap();
+        assert_eq!(address, "/ltc/timecode");
+        assert_eq!(
+            arguments,
+            [
+                OSCArgument::String("01:00:00:00".to_owned()),
+                OSCArgument::Int(-2),
+                OSCArgument::Float(1.5)
+            ]
+        );
+        assert!(parse_osc_message(&message[..38]).is_err());
+        assert!(parse_osc_message(b"ltc\0,\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_localhost() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let sender = OSCSender::try_new(&OSCSenderConfig {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            target: receiver.local_addr().unwrap(),
+            rate: FrameRate::FPS_29_97_DF,
+            lock_address: String::new(),
+            speed_address: "/show/speed".to_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let timecode: SMPTETimecode = "00:01:00:02".parse().unwrap();
+        sender.send_timecode(&timecode).unwrap();
+        sender.send_user_bits(0xDEADBEEF).unwrap();
+        sender.send_lock_state(LockState::Locked).unwrap();
+        sender.send_speed(-1.0).unwrap();
+
+        let mut buf = [0u8; 256];
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let len = receiver.recv(&mut buf).unwrap();
+            received.push(parse_osc_message(&buf[..len]).unwrap());
+        }
+        assert_eq!(
+            received,
+            [
+                (
+                    "/ltc/timecode".to_owned(),
+                    vec![OSCArgument::String("00:01:00;02".to_owned())]
+                ),
+                (
+                    "/ltc/userbits".to_owned(),
+                    vec![OSCArgument::Int(0xDEADBEEFu32 as i32)]
+                ),
+                ("/show/speed".to_owned(), vec![OSCArgument::Float(-1.0)]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+// Spans of timecode, e.g. a take between two LTC dropouts or an EDL event. The start is part of
+// the range, the end isn't. A range whose end is before its start runs through midnight, so
+// 23:59:00:00 to 00:01:00:00 is two minutes long. Only the time of day counts: dates aren't
+// compared, and the timecodes a range makes up (its frames, intersections and unions) have none.
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+
+#[derive(Debug, Clone)]
+pub struct TimecodeRange {
+    pub start: SMPTETimecode,
+    pub end: SMPTETimecode,
+    pub rate: FrameRate,
+}
+
+impl TimecodeRange {
+    pub fn new(start: SMPTETimecode, end: SMPTETimecode, rate: FrameRate) -> Self {
+        TimecodeRange { start, end, rate }
+    }
+
+    // `duration` frames from `start`, None unless that's at least 0 and less than a day
+    pub fn from_duration(start: SMPTETimecode, duration: i64, rate: FrameRate) -> Option<Self> {
+        if !(0..rate.frames_per_day()).contains(&duration) {
+            return None;
+        }
+        let end = start.with_frames(start.to_frames(rate) + duration, rate);
+        Some(TimecodeRange { start, end, rate })
+    }
+
+    // Start and end in frames since the midnight before the start, the end may be past the
+    // next midnight
+    fn bounds(&self) -> (i64, i64) {
+        let start = self.start.to_frames(self.rate);
+        let mut end = self.end.to_frames(self.rate);
+        if end < start {
+            end += self.rate.frames_per_day();
+        }
+        (start, end)
+    }
+
+    fn with_bounds(&self, start: i64, end: i64) -> Self {
+        TimecodeRange {
+            start: SMPTETimecode::from_frames(start, self.rate),
+            end: SMPTETimecode::from_frames(end, self.rate),
+            rate: self.rate,
+        }
+    }
+
+    // Length in frames
+    pub fn duration(&self) -> i64 {
+        let (start, end) = self.bounds();
+        end - start
+    }
+
+    pub fn duration_secs(&self) -> f64 {
+        self.duration() as f64 / self.rate.fps()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.duration() == 0
+    }
+
+    pub fn crosses_midnight(&self) -> bool {
+        let (_, end) = self.bounds();
+        end > self.rate.frames_per_day()
+    }
+
+    pub fn contains(&self, timecode: &SMPTETimecode) -> bool {
+        let (start, end) = self.bounds();
+        let mut frames = timecode.to_frames(self.rate);
+        if frames < start {
+            frames += self.rate.frames_per_day();
+        }
+        frames < end
+    }
+
+    // The bounds of `other` moved by whole days to wherever they line up with `self`
+    fn aligned(&self, other: &TimecodeRange) -> impl Iterator<Item = (i64, i64)> {
+        let day = self.rate.frames_per_day();
+        let (start, end) =
+            TimecodeRange::new(other.start.clone(), other.end.clone(), self.rate).bounds();
+        [-day, 0, day]
+            .into_iter()
+            .map(move |shift| (start + shift, end + shift))
+    }
+
+    pub fn overlaps(&self, other: &TimecodeRange) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    // The frames in both ranges, None if there are none. Two ranges that together cover more
+    // than a day can overlap at both ends, then this is the overlap nearest the start of `self`.
+    // `other` is read at the rate of `self`.
+    pub fn intersection(&self, other: &TimecodeRange) -> Option<TimecodeRange> {
+        let (start, end) = self.bounds();
+        self.aligned(other)
+            .map(|(other_start, other_end)| (start.max(other_start), end.min(other_end)))
+            .find(|(start, end)| start < end)
+            .map(|(start, end)| self.with_bounds(start, end))
+    }
+
+    // The range covering both, None if there's a gap between them or they'd cover a whole day
+    pub fn union(&self, other: &TimecodeRange) -> Option<TimecodeRange> {
+        let (start, end) = self.bounds();
+        let (start, end) = self
+            .aligned(other)
+            .filter(|&(other_start, other_end)| other_start <= end && start <= other_end)
+            .map(|(other_start, other_end)| (start.min(other_start), end.max(other_end)))
+            .next()?;
+        if end - start >= self.rate.frames_per_day() {
+            return None;
+        }
+        Some(self.with_bounds(start, end))
+    }
+
+    // Every frame of the range in order
+    pub fn iter(&self) -> impl Iterator<Item = SMPTETimecode> {
+        let (start, end) = self.bounds();
+        let rate = self.rate;
+        (start..end).map(move |frames| SMPTETimecode::from_frames(frames, rate))
+    }
+
+    // The part before midnight and the part after it, if the range runs through midnight. The
+    // part after starts at 00:00:00:00 with the date of the end.
+    pub fn split_at_midnight(&self) -> (TimecodeRange, Option<TimecodeRange>) {
+        if !self.crosses_midnight() {
+            return (self.clone(), None);
+        }
+        let midnight = self.end.with_frames(0, self.rate);
+        (
+            TimecodeRange::new(self.start.clone(), midnight.clone(), self.rate),
+            Some(TimecodeRange::new(midnight, self.end.clone(), self.rate)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: &str, end: &str) -> TimecodeRange {
+        TimecodeRange::new(
+            start.parse().unwrap(),
+            end.parse().unwrap(),
+            FrameRate::FPS_25,
+        )
+    }
+
+    fn labels(range: &TimecodeRange) -> (String, String) {
+        (
+            range.start.to_time_string(false),
+            range.end.to_time_string(false),
+        )
+    }
+
+    #[test]
+    fn test_range() {
+        let take = range("01:00:00:00", "01:00:10:00");
+        assert_eq!(take.duration(), 250);
+        assert_eq!(take.duration_secs(), 10.0);
+        assert!(take.contains(&"01:00:00:00".parse().unwrap()));
+        assert!(take.contains(&"01:00:09:24".parse().unwrap()));
+        assert!(!take.contains(&"01:00:10:00".parse().unwrap()));
+        assert!(range("01:00:00:00", "01:00:00:00").is_empty());
+
+        let frames: Vec<_> = range("00:59:59:23", "01:00:00:02")
+            .iter()
+            .map(|timecode| timecode.to_time_string(false))
+            .collect();
+        assert_eq!(
+            frames,
+            ["00:59:59:23", "00:59:59:24", "01:00:00:00", "01:00:00:01"]
+        );
+
+        let rate = FrameRate::FPS_29_97_DF;
+        let event = TimecodeRange::from_duration("00:00:59;28".parse().unwrap(), 4, rate).unwrap();
+        assert_eq!(event.end.to_time_string(true), "00:01:00;04");
+        assert_eq!(event.iter().count(), 4);
+        let start: SMPTETimecode = "10:00:00;00".parse().unwrap();
+        for duration in [-1, rate.frames_per_day(), i64::MAX] {
+            assert!(TimecodeRange::from_duration(start.clone(), duration, rate).is_none());
+        }
+    }
+
+    #[test]
+    fn test_overlap() {
+        let a = range("01:00:00:00", "01:00:10:00");
+        let b = range("01:00:05:00", "01:00:20:00");
+        let c = range("01:00:10:00", "01:00:15:00");
+        assert!(a.overlaps(&b));
+        assert_eq!(
+            labels(&a.intersection(&b).unwrap()),
+            ("01:00:05:00".to_owned(), "01:00:10:00".to_owned())
+        );
+        assert_eq!(
+            labels(&a.union(&b).unwrap()),
+            ("01:00:00:00".to_owned(), "01:00:20:00".to_owned())
+        );
+        // Adjacent ranges join but don't overlap
+        assert!(!a.overlaps(&c));
+        assert!(a.intersection(&c).is_none());
+        assert_eq!(a.union(&c).unwrap().duration(), 15 * 25);
+        assert!(a.union(&range("02:00:00:00", "02:00:01:00")).is_none());
+
+        let night = range("23:59:00:00", "00:01:00:00");
+        let morning = range("00:00:30:00", "00:05:00:00");
+        assert_eq!(
+            labels(&night.intersection(&morning).unwrap()),
+            ("00:00:30:00".to_owned(), "00:01:00:00".to_owned())
+        );
+        assert_eq!(
+            labels(&morning.union(&night).unwrap()),
+            ("23:59:00:00".to_owned(), "00:05:00:00".to_owned())
+        );
+        assert!(range("12:00:00:00", "23:00:00:00")
+            .union(&range("22:00:00:00", "13:00:00:00"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_midnight() {
+        let night = range("23:59:59:00", "00:00:01:00");
+        assert!(night.crosses_midnight());
+        assert_eq!(night.duration(), 50);
+        assert!(night.contains(&"00:00:00:00".parse().unwrap()));
+        assert!(!night.contains(&"00:00:01:00".parse().unwrap()));
+        assert!(!night.contains(&"23:59:58:24".parse().unwrap()));
+
+        let (before, after) = night.split_at_midnight();
+        let after = after.unwrap();
+        assert_eq!(
+            labels(&before),
+            ("23:59:59:00".to_owned(), "00:00:00:00".to_owned())
+        );
+        assert_eq!(before.duration() + after.duration(), 50);
+        assert!(!before.crosses_midnight());
+        assert_eq!(after.start.to_time_string(false), "00:00:00:00");
+
+        // Ending right at midnight doesn't cross it
+        let evening = range("23:00:00:00", "00:00:00:00");
+        assert!(!evening.crosses_midnight());
+        assert!(evening.split_at_midnight().1.is_none());
+        assert_eq!(
+            evening.iter().last().unwrap().to_time_string(false),
+            "23:59:59:24"
+        );
+
+        // A date on the start doesn't end up on frames after midnight
+        let dated = SMPTETimecode::new(Default::default(), 24, 12, 31, 23, 59, 59, 24);
+        let night = TimecodeRange::new(dated, "00:00:00:01".parse().unwrap(), FrameRate::FPS_25);
+        let frames: Vec<_> = night.iter().collect();
+        assert_eq!(frames[1].to_time_string(false), "00:00:00:00");
+        assert_eq!((frames[1].years(), frames[1].days()), (0, 0));
+        let union = night.union(&range("00:00:00:00", "00:00:01:00")).unwrap();
+        assert_eq!((union.end.years(), union.end.days()), (0, 0));
+    }
+}
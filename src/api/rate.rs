@@ -0,0 +1,352 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::LTCTVStandard;
+use crate::error::TimecodeError;
+
+// Frame rates are kept as exact fractions so that sample positions can be computed without
+// accumulating floating point error over long streams
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FrameRate {
+    numerator: u32,
+    denominator: u32,
+    drop_frame: bool,
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate::FPS_25
+    }
+}
+
+impl FrameRate {
+    pub const FPS_23_976: FrameRate = FrameRate::new(24_000, 1001, false);
+    pub const FPS_24: FrameRate = FrameRate::new(24, 1, false);
+    pub const FPS_25: FrameRate = FrameRate::new(25, 1, false);
+    pub const FPS_29_97: FrameRate = FrameRate::new(30_000, 1001, false);
+    pub const FPS_29_97_DF: FrameRate = FrameRate::new(30_000, 1001, true);
+    pub const FPS_30: FrameRate = FrameRate::new(30, 1, false);
+    pub const FPS_48: FrameRate = FrameRate::new(48, 1, false);
+    pub const FPS_50: FrameRate = FrameRate::new(50, 1, false);
+    pub const FPS_59_94: FrameRate = FrameRate::new(60_000, 1001, false);
+    pub const FPS_59_94_DF: FrameRate = FrameRate::new(60_000, 1001, true);
+    pub const FPS_60: FrameRate = FrameRate::new(60, 1, false);
+
+    pub const fn new(numerator: u32, denominator: u32, drop_frame: bool) -> Self {
+        FrameRate {
+            numerator,
+            denominator,
+            drop_frame,
+        }
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    pub fn is_drop_frame(&self) -> bool {
+        self.drop_frame
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    // The number of frame labels per timecode second, e.g. 30 for 29.97
+    pub fn timebase(&self) -> u32 {
+        self.numerator.div_ceil(self.denominator)
+    }
+
+    pub fn standard(&self) -> LTCTVStandard {
+        match self.timebase() {
+            24 | 48 => LTCTVStandard::LTCTV_FILM_24,
+            25 | 50 => LTCTVStandard::LTCTV_625_50,
+            _ => LTCTVStandard::LTCTV_525_60,
+        }
+    }
+
+    // Above 30 fps LTC counts frame pairs, see `hfr`
+    pub fn is_high_frame_rate(&self) -> bool {
+        self.timebase() > 30
+    }
+
+    // The rate of the frame pairs, e.g. 29.97 drop frame for 59.94 drop frame
+    pub fn pair_rate(&self) -> FrameRate {
+        if self.numerator.is_multiple_of(2) {
+            FrameRate::new(self.numerator / 2, self.denominator, self.drop_frame)
+        } else {
+            FrameRate::new(self.numerator, self.denominator * 2, self.drop_frame)
+        }
+    }
+
+    pub fn samples_per_frame(&self, sample_rate: f64) -> f64 {
+        sample_rate * self.denominator as f64 / self.numerator as f64
+    }
+
+    // The first sample of `frame` in a stream where frame 0 starts at sample 0. Frame lengths
+    // follow the exact fraction, e.g. 8008 samples every 5 frames at 29.97 and 48 kHz.
+    pub fn frame_start(&self, frame: i64, sample_rate: f64) -> i64 {
+        (frame as f64 * sample_rate * self.denominator as f64 / self.numerator as f64).round()
+            as i64
+    }
+
+    // The two bit rate type MTC and Art-Net share: 24, 25, 30 drop frame and 30 fps. 23.976 and
+    // 29.97 non drop frame go out as 24 and 30, the receiver just runs slow.
+    pub(crate) fn smpte_type(&self) -> Option<u8> {
+        match (self.timebase(), self.drop_frame) {
+            (24, false) => Some(0),
+            (25, false) => Some(1),
+            (30, true) => Some(2),
+            (30, false) => Some(3),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_smpte_type(code: u8) -> Self {
+        match code & 0x03 {
+            0 => FrameRate::FPS_24,
+            1 => FrameRate::FPS_25,
+            2 => FrameRate::FPS_29_97_DF,
+            _ => FrameRate::FPS_30,
+        }
+    }
+
+    // Labels skipped at the start of every minute that is not a multiple of ten
+    fn dropped_per_minute(&self) -> i64 {
+        if self.drop_frame {
+            self.timebase() as i64 / 15
+        } else {
+            0
+        }
+    }
+
+    pub fn frames_per_day(&self) -> i64 {
+        let timebase = self.timebase() as i64;
+        timebase * 86_400 - self.dropped_per_minute() * 24 * 54
+    }
+
+    // Whether drop frame skips this label, e.g. 00:01:00;00 and 00:01:00;01 at 29.97 and
+    // 00:01:00;00 to 00:01:00;03 at 59.94
+    pub fn is_dropped_label(&self, minutes: u8, seconds: u8, frame: u8) -> bool {
+        !minutes.is_multiple_of(10) && seconds == 0 && (frame as i64) < self.dropped_per_minute()
+    }
+
+    // Frames since midnight of a label. The label has to exist, a dropped one gives the count
+    // of a frame before it.
+    pub fn hmsf_to_frames(&self, hours: u8, minutes: u8, seconds: u8, frame: u8) -> i64 {
+        let timebase = self.timebase() as i64;
+        let total_minutes = 60 * hours as i64 + minutes as i64;
+        (total_minutes * 60 + seconds as i64) * timebase + frame as i64
+            - self.dropped_per_minute() * (total_minutes - total_minutes / 10)
+    }
+
+    // The label of a frame count, counts outside of a day wrap around midnight
+    pub fn frames_to_hmsf(&self, frames: i64) -> (u8, u8, u8, u8) {
+        let timebase = self.timebase() as i64;
+        let mut frames = frames.rem_euclid(self.frames_per_day());
+
+        let drop = self.dropped_per_minute();
+        if drop > 0 {
+            let per_ten_minutes = timebase * 600 - 9 * drop;
+            let per_minute = timebase * 60 - drop;
+            let tens = frames / per_ten_minutes;
+            let rest = frames % per_ten_minutes;
+            frames += 9 * drop * tens;
+            if rest > drop {
+                frames += drop * ((rest - drop) / per_minute);
+            }
+        }
+
+        let frame = frames % timebase;
+        let seconds = frames / timebase % 60;
+        let minutes = frames / (timebase * 60) % 60;
+        let hours = frames / (timebase * 3600) % 24;
+        (hours as u8, minutes as u8, seconds as u8, frame as u8)
+    }
+}
+
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)?;
+        } else if self.denominator == 1001 && self.numerator.is_multiple_of(1000) {
+            // 29.97, 23.976, 59.94
+            let fps = format!("{:.3}", self.fps());
+            write!(f, "{}", fps.trim_end_matches('0'))?;
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)?;
+        }
+        if self.drop_frame {
+            write!(f, "df")?;
+        }
+        Ok(())
+    }
+}
+
+// Parses "25", "29.97", "29.97df", "23.976" or "30000/1001". Rates just below a whole number
+// are taken as the NTSC x/1.001 rate.
+impl FromStr for FrameRate {
+    type Err = TimecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (value, drop_frame) = match s.strip_suffix("df") {
+            Some(value) => (value.trim_end(), true),
+            None => (s.as_str(), false),
+        };
+
+        let (numerator, denominator) = if let Some((numerator, denominator)) = value.split_once('/')
+        {
+            let numerator = numerator.trim().parse::<u32>();
+            let denominator = denominator.trim().parse::<u32>();
+            match (numerator, denominator) {
+                (Ok(numerator), Ok(denominator)) => (numerator, denominator),
+                _ => return Err(TimecodeError::ParseFrameRate),
+            }
+        } else {
+            let fps = value
+                .parse::<f64>()
+                .map_err(|_| TimecodeError::ParseFrameRate)?;
+            let timebase = fps.ceil();
+            if fps.fract() == 0.0 {
+                (fps as u32, 1)
+            } else if (timebase * 1000.0 / 1001.0 - fps).abs() < 0.01 {
+                (timebase as u32 * 1000, 1001)
+            } else {
+                return Err(TimecodeError::ParseFrameRate);
+            }
+        };
+
+        let rate = FrameRate::new(numerator, denominator, drop_frame);
+        // Drop frame only exists for the 30 and 60 frame timebases
+        if numerator == 0 || denominator == 0 || drop_frame && !rate.timebase().is_multiple_of(30) {
+            return Err(TimecodeError::ParseFrameRate);
+        }
+        Ok(rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SMPTETimecode;
+
+    #[test]
+    fn test_parse_frame_rate() {
+        for (text, rate) in [
+            ("23.976", FrameRate::FPS_23_976),
+            ("24", FrameRate::FPS_24),
+            ("25", FrameRate::FPS_25),
+            ("29.97", FrameRate::FPS_29_97),
+            ("29.97df", FrameRate::FPS_29_97_DF),
+            ("30", FrameRate::FPS_30),
+            ("59.94df", FrameRate::FPS_59_94_DF),
+        ] {
+            assert_eq!(text.parse::<FrameRate>().unwrap(), rate);
+            assert_eq!(rate.to_string(), text);
+        }
+        assert_eq!(
+            "30000/1001 DF".parse::<FrameRate>().unwrap(),
+            FrameRate::FPS_29_97_DF
+        );
+        assert!("25df".parse::<FrameRate>().is_err());
+        assert!("27.5".parse::<FrameRate>().is_err());
+    }
+
+    #[test]
+    fn test_parse_timecode() {
+        let timecode: SMPTETimecode = "10:01:00;02".parse().unwrap();
+        assert_eq!(
+            (
+                timecode.hours(),
+                timecode.minutes(),
+                timecode.seconds(),
+                timecode.frame()
+            ),
+            (10, 1, 0, 2)
+        );
+        assert!("24:00:00:00".parse::<SMPTETimecode>().is_err());
+        assert!("10:00:00".parse::<SMPTETimecode>().is_err());
+        assert!("10:00:00:00:00".parse::<SMPTETimecode>().is_err());
+    }
+
+    #[test]
+    fn test_drop_frame_minutes() {
+        for (rate, dropped, per_day) in [
+            (FrameRate::FPS_29_97_DF, 2, 2_589_408),
+            (FrameRate::FPS_59_94_DF, 4, 5_178_816),
+        ] {
+            let timebase = rate.timebase() as i64;
+            assert_eq!(rate.frames_per_day(), per_day);
+            for minute in 0..24 * 60 {
+                let (hours, minutes) = ((minute / 60) as u8, (minute % 60) as u8);
+                let first_label = if minutes % 10 == 0 { 0 } else { dropped as u8 };
+                // Labels skipped up to and including the start of this minute
+                let skipped = dropped * (minute - minute / 10);
+                let first = minute * 60 * timebase + first_label as i64 - skipped;
+
+                assert_eq!(rate.frames_to_hmsf(first), (hours, minutes, 0, first_label));
+                assert_eq!(rate.hmsf_to_frames(hours, minutes, 0, first_label), first);
+                for frame in 0..first_label {
+                    assert!(rate.is_dropped_label(minutes, 0, frame));
+                }
+                assert!(!rate.is_dropped_label(minutes, 0, first_label));
+
+                // The frame before is the last one of the previous minute
+                let (previous_hours, previous_minutes) = if minute == 0 {
+                    (23, 59)
+                } else {
+                    (((minute - 1) / 60) as u8, ((minute - 1) % 60) as u8)
+                };
+                let last = (timebase - 1) as u8;
+                assert_eq!(
+                    rate.frames_to_hmsf(first - 1),
+                    (previous_hours, previous_minutes, 59, last)
+                );
+                assert_eq!(
+                    rate.hmsf_to_frames(previous_hours, previous_minutes, 59, last),
+                    (first - 1).rem_euclid(per_day)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_day() {
+        for rate in [FrameRate::FPS_29_97_DF, FrameRate::FPS_59_94_DF] {
+            let mut expected = (0, 0, 0, 0);
+            for frames in 0..rate.frames_per_day() {
+                let (hours, minutes, seconds, frame) = rate.frames_to_hmsf(frames);
+                assert_eq!((hours, minutes, seconds, frame), expected);
+                assert_eq!(rate.hmsf_to_frames(hours, minutes, seconds, frame), frames);
+
+                // Count the next label by hand, skipping the dropped ones
+                expected.3 += 1;
+                if expected.3 as u32 == rate.timebase() {
+                    expected = (expected.0, expected.1, expected.2 + 1, 0);
+                }
+                if expected.2 == 60 {
+                    expected = (expected.0, expected.1 + 1, 0, 0);
+                }
+                if expected.1 == 60 {
+                    expected = (expected.0 + 1, 0, 0, 0);
+                }
+                while rate.is_dropped_label(expected.1, expected.2, expected.3) {
+                    expected.3 += 1;
+                }
+            }
+            assert_eq!(rate.frames_to_hmsf(rate.frames_per_day()), (0, 0, 0, 0));
+        }
+
+        // Through the timecode too, against a count worked out by hand
+        let rate = FrameRate::FPS_29_97_DF;
+        let timecode: SMPTETimecode = "01:10:00;02".parse().unwrap();
+        assert_eq!(timecode.to_frames(rate), 125_876);
+        let back = SMPTETimecode::from_frames(125_876, rate);
+        assert_eq!(back.to_time_string(true), "01:10:00;02");
+    }
+}
@@ -0,0 +1,37 @@
+use super::consts::SampleType;
+
+// Sample formats the generators can write. The conversions mirror the ones the decoder's
+// write_* functions use to get back to libltc's 8 bit unsigned samples.
+pub trait Sample: Copy {
+    fn from_ltc(sample: SampleType) -> Self;
+}
+
+impl Sample for u8 {
+    fn from_ltc(sample: SampleType) -> Self {
+        sample
+    }
+}
+
+impl Sample for u16 {
+    fn from_ltc(sample: SampleType) -> Self {
+        (sample as u16) << 8
+    }
+}
+
+impl Sample for i16 {
+    fn from_ltc(sample: SampleType) -> Self {
+        (sample as i16 - 128) << 8
+    }
+}
+
+impl Sample for f32 {
+    fn from_ltc(sample: SampleType) -> Self {
+        (sample as f32 - 128.0) / 127.0
+    }
+}
+
+impl Sample for f64 {
+    fn from_ltc(sample: SampleType) -> Self {
+        (sample as f64 - 128.0) / 127.0
+    }
+}
@@ -0,0 +1,283 @@
+// serde support, enabled with the `serde` feature.
+// Human readable formats get strings: timecodes as "HH:MM:SS:FF" (with the
+// date as "YY-MM-DD HH:MM:SS:FF +HHMM" when there is one), timezones as
+// "+HHMM" and LTC frames as the hex of their 10 bytes. Binary formats get the
+// fields and the raw bytes.
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::consts::SampleType;
+use super::frame::{LTCFrame, LTCFrameExt};
+use super::{SMPTETimecode, Timezone};
+
+impl Serialize for Timezone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Raw timezones from libltc aren't guaranteed to be text
+        let bytes: Vec<u8> = self
+            .to_raw()
+            .iter()
+            .map(|&byte| byte as u8)
+            .take_while(|&byte| byte != 0)
+            .collect();
+        let timezone = std::str::from_utf8(&bytes).map_err(ser::Error::custom)?;
+        serializer.serialize_str(timezone)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timezone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Timezone::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TimecodeFields {
+    timezone: Timezone,
+    years: u8,
+    months: u8,
+    days: u8,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+    frame: u8,
+}
+
+fn has_date(timecode: &SMPTETimecode) -> bool {
+    let timezone = timecode.timezone().to_raw();
+    timecode.years() != 0
+        || timecode.months() != 0
+        || timecode.days() != 0
+        || timezone != Timezone::default().to_raw()
+}
+
+// YY-MM-DD HH:MM:SS:FF +HHMM
+fn parse_dated(s: &str) -> Option<SMPTETimecode> {
+    let mut parts = s.split_whitespace();
+    let (date, time, timezone) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let mut date = date.split('-').map(|field| field.parse::<u8>().ok());
+    let (years, months, days) = (date.next()??, date.next()??, date.next()??);
+    if date.next().is_some() || years > 99 || months > 12 || days > 31 {
+        return None;
+    }
+    let time = SMPTETimecode::from_str(time).ok()?;
+    Some(SMPTETimecode::new(
+        timezone.parse().ok()?,
+        years,
+        months,
+        days,
+        time.hours(),
+        time.minutes(),
+        time.seconds(),
+        time.frame(),
+    ))
+}
+
+impl Serialize for SMPTETimecode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let time = self.to_time_string(false);
+            if has_date(self) {
+                serializer.collect_str(&format_args!(
+                    "{:02}-{:02}-{:02} {} {}",
+                    self.years(),
+                    self.months(),
+                    self.days(),
+                    time,
+                    self.timezone().to_string().trim_end_matches('\0')
+                ))
+            } else {
+                serializer.serialize_str(&time)
+            }
+        } else {
+            TimecodeFields {
+                timezone: self.timezone(),
+                years: self.years(),
+                months: self.months(),
+                days: self.days(),
+                hours: self.hours(),
+                minutes: self.minutes(),
+                seconds: self.seconds(),
+                frame: self.frame(),
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SMPTETimecode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            if s.split_whitespace().count() > 1 {
+                parse_dated(&s).ok_or_else(|| {
+                    de::Error::custom("Invalid timecode, expected YY-MM-DD HH:MM:SS:FF +HHMM")
+                })
+            } else {
+                SMPTETimecode::from_str(&s).map_err(de::Error::custom)
+            }
+        } else {
+            let fields = TimecodeFields::deserialize(deserializer)?;
+            Ok(SMPTETimecode::new(
+                fields.timezone,
+                fields.years,
+                fields.months,
+                fields.days,
+                fields.hours,
+                fields.minutes,
+                fields.seconds,
+                fields.frame,
+            ))
+        }
+    }
+}
+
+impl Serialize for LTCFrame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = self.to_bytes();
+        if serializer.is_human_readable() {
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            serializer.serialize_str(&hex)
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LTCFrame {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            let hex = String::deserialize(deserializer)?;
+            let error = || de::Error::custom("Invalid LTC frame, expected 20 hex digits");
+            if hex.len() != 20 || !hex.is_ascii() {
+                return Err(error());
+            }
+            let mut bytes = [0u8; 10];
+            for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+                let digits = std::str::from_utf8(digits).map_err(|_| error())?;
+                *byte = u8::from_str_radix(digits, 16).map_err(|_| error())?;
+            }
+            bytes
+        } else {
+            <[u8; 10]>::deserialize(deserializer)?
+        };
+        Ok(LTCFrame::from_bytes(bytes))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FrameExtFields {
+    ltc: LTCFrame,
+    off_start: i64,
+    off_end: i64,
+    reverse: bool,
+    biphase_tics: Vec<f32>,
+    sample_min: SampleType,
+    sample_max: SampleType,
+    volume: f64,
+}
+
+impl Serialize for LTCFrameExt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FrameExtFields {
+            ltc: self.ltc(),
+            off_start: self.off_start(),
+            off_end: self.off_end(),
+            reverse: self.reverse(),
+            biphase_tics: self.biphase_tics().to_vec(),
+            sample_min: self.sample_min(),
+            sample_max: self.sample_max(),
+            volume: self.volume(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LTCFrameExt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = FrameExtFields::deserialize(deserializer)?;
+        let biphase_tics = <[f32; 80]>::try_from(fields.biphase_tics.as_slice())
+            .map_err(|_| de::Error::invalid_length(fields.biphase_tics.len(), &"80 tics"))?;
+        let frame = LTCFrameExt::default();
+        frame.set_ltc(fields.ltc);
+        frame.set_off_start(fields.off_start);
+        frame.set_off_end(fields.off_end);
+        frame.set_reverse(fields.reverse);
+        frame.set_biphase_tics(biphase_tics);
+        frame.set_sample_min(fields.sample_min);
+        frame.set_sample_max(fields.sample_max);
+        frame.set_volume(fields.volume);
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::consts::{LtcBgFlags, LtcBgFlagsKind};
+    use crate::api::LTCTVStandard;
+
+    #[test]
+    fn test_timecode_json() {
+        let timecode: SMPTETimecode = "01:02:03:04".parse().unwrap();
+        let json = serde_json::to_string(&timecode).unwrap();
+        assert_eq!(json, "\"01:02:03:04\"");
+        let back: SMPTETimecode = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_time_string(false), "01:02:03:04");
+
+        let dated = SMPTETimecode::new("-0130".parse().unwrap(), 24, 12, 31, 23, 59, 59, 24);
+        let json = serde_json::to_string(&dated).unwrap();
+        assert_eq!(json, "\"24-12-31 23:59:59:24 -0130\"");
+        let back: SMPTETimecode = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_string(), dated.to_string());
+
+        assert!(serde_json::from_str::<SMPTETimecode>("\"25:00:00:00\"").is_err());
+        assert!(serde_json::from_str::<SMPTETimecode>("\"24-13-01 00:00:00:00 +0000\"").is_err());
+        assert!(serde_json::from_str::<Timezone>("\"0100\"").is_err());
+        assert!(serde_json::to_string(&Timezone::new([-1, 0, 0, 0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_frame_json() {
+        let timecode: SMPTETimecode = "10:20:30:12".parse().unwrap();
+        let mut ltc = LTCFrame::from_timecode(
+            &timecode,
+            LTCTVStandard::LTCTV_625_50,
+            LtcBgFlags::default(),
+        );
+        ltc.set_parity(LTCTVStandard::LTCTV_625_50);
+        let json = serde_json::to_string(&ltc).unwrap();
+        assert_eq!(json.len(), 22);
+        let back: LTCFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_bytes(), ltc.to_bytes());
+        assert!(serde_json::from_str::<LTCFrame>("\"00\"").is_err());
+
+        let frame = LTCFrameExt::default();
+        frame.set_ltc(ltc);
+        frame.set_off_start(480);
+        frame.set_off_end(2399);
+        frame.set_reverse(true);
+        frame.set_biphase_tics([12.5; 80]);
+        frame.set_volume(-18.0);
+        let json = serde_json::to_string(&frame).unwrap();
+        let back: LTCFrameExt = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.ltc().to_bytes(), ltc.to_bytes());
+        assert_eq!(back.off_start(), 480);
+        assert_eq!(back.off_end(), 2399);
+        assert!(back.reverse());
+        assert_eq!(back.biphase_tics(), [12.5; 80]);
+        assert_eq!(back.volume(), -18.0);
+
+        let flags = *LtcBgFlags::default().set(LtcBgFlagsKind::LTC_USE_DATE);
+        let json = serde_json::to_string(&(flags, LTCTVStandard::LTCTV_625_50)).unwrap();
+        assert_eq!(json, "[1,\"LTCTV_625_50\"]");
+        let back: (LtcBgFlags, LTCTVStandard) = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.0, flags);
+    }
+}
@@ -0,0 +1,274 @@
+use super::consts::SampleType;
+use super::encoder::LTCEncoder;
+use super::generator::{encoder_for, LTCGeneratorConfig};
+use super::SMPTETimecode;
+use crate::error::LTCEncoderError;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShuttleConfig {
+    pub generator: LTCGeneratorConfig,
+    // Slower than this the transport counts as stopped and only silence comes out
+    pub min_speed: f64,
+    // Faster speeds are clamped to this
+    pub max_speed: f64,
+}
+
+impl Default for ShuttleConfig {
+    fn default() -> Self {
+        ShuttleConfig {
+            generator: LTCGeneratorConfig::default(),
+            min_speed: 0.1,
+            max_speed: 10.0,
+        }
+    }
+}
+
+// Emulates a tape transport: every frame is played at its own speed, negative speeds play it
+// backwards with the bytes and bits in reverse order and count the timecode down.
+#[derive(Debug)]
+pub struct ShuttleGenerator {
+    config: ShuttleConfig,
+    encoder: LTCEncoder,
+    // Direction of the last frame played, None before the first one
+    reverse: Option<bool>,
+    // Samples rendered so far
+    samples: u64,
+}
+
+impl ShuttleGenerator {
+    pub fn try_new(config: &ShuttleConfig, start: &SMPTETimecode) -> Result<Self, LTCEncoderError> {
+        let generator = &config.generator;
+        let mut encoder = encoder_for(generator)?;
+        encoder.set_timecode(start);
+
+        // Bytes are flushed one at a time, so the slowest byte has to fit the encoder buffer
+        let samples_per_frame = generator.rate.samples_per_frame(generator.sample_rate);
+        let valid = config.max_speed > 0.0
+            && config.min_speed > 0.0
+            && config.min_speed <= config.max_speed
+            && samples_per_frame / 10.0 / config.min_speed + 1.0 <= encoder.get_buffersize() as f64;
+        if !valid {
+            return Err(LTCEncoderError::ConfigError);
+        }
+
+        Ok(ShuttleGenerator {
+            config: *config,
+            encoder,
+            reverse: None,
+            samples: 0,
+        })
+    }
+
+    pub fn config(&self) -> &ShuttleConfig {
+        &self.config
+    }
+
+    // The frame played next in the current direction
+    pub fn timecode(&self) -> SMPTETimecode {
+        self.encoder.get_timecode()
+    }
+
+    pub fn set_timecode(&mut self, timecode: &SMPTETimecode) {
+        self.encoder.set_timecode(timecode);
+        self.reverse = None;
+    }
+
+    pub fn set_user_bits(&mut self, data: u32) {
+        self.encoder.set_user_bits(data);
+    }
+
+    // Seconds of audio rendered so far
+    pub fn elapsed(&self) -> f64 {
+        self.samples as f64 / self.config.generator.sample_rate
+    }
+
+    // Appends one frame played at `speed` to `out`. A speed that is NaN or infinite is an
+    // error and appends nothing.
+    pub fn encode_frame(
+        &mut self,
+        speed: f64,
+        out: &mut Vec<SampleType>,
+    ) -> Result<(), LTCEncoderError> {
+        if !speed.is_finite() {
+            return Err(LTCEncoderError::EncodeError);
+        }
+        let start = out.len();
+        let speed = speed.clamp(-self.config.max_speed, self.config.max_speed);
+
+        if speed.abs() < self.config.min_speed {
+            let samples_per_frame = self
+                .config
+                .generator
+                .rate
+                .samples_per_frame(self.config.generator.sample_rate);
+            out.resize(start + samples_per_frame.round() as usize, 128);
+            self.samples += (out.len() - start) as u64;
+            return Ok(());
+        }
+
+        let reverse = speed < 0.0;
+        if self.reverse.is_some_and(|last| last != reverse) {
+            // Changing direction plays the frame that just went by again, the other way
+            if reverse {
+                self.encoder.dec_timecode()?;
+            } else {
+                self.encoder.inc_timecode()?;
+            }
+        }
+        self.reverse = Some(reverse);
+
+        // libltc stretches by the inverse, 2.0 plays at half speed. Flushing after every byte
+        // keeps slow frames from overflowing the encoder buffer.
+        for i in 0..10 {
+            let byte = if reverse { 9 - i } else { i };
+            self.encoder.encode_byte(byte, 1.0 / speed)?;
+            let (buf, len) = self.encoder.get_buf_ref(true);
+            out.extend_from_slice(&buf[..len]);
+        }
+
+        if reverse {
+            self.encoder.dec_timecode()?;
+        } else {
+            self.encoder.inc_timecode()?;
+        }
+        self.samples += (out.len() - start) as u64;
+        Ok(())
+    }
+
+    // Renders `duration` seconds, asking `curve` for the speed at the start of every frame with
+    // the seconds elapsed since the start of this call
+    pub fn render<F: FnMut(f64) -> f64>(
+        &mut self,
+        duration: f64,
+        mut curve: F,
+    ) -> Result<Vec<SampleType>, LTCEncoderError> {
+        let sample_rate = self.config.generator.sample_rate;
+        let total = (duration * sample_rate).round() as usize;
+        let mut out = Vec::with_capacity(total);
+        while out.len() < total {
+            let speed = curve(out.len() as f64 / sample_rate);
+            self.encode_frame(speed, &mut out)?;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::consts::LtcBgFlags;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::rate::FrameRate;
+    use crate::api::speed::{SpeedEstimator, SpeedEstimatorConfig};
+    use crate::api::Timezone;
+
+    fn shuttle() -> ShuttleGenerator {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 1, 0, 0, 0);
+        ShuttleGenerator::try_new(&ShuttleConfig::default(), &start).unwrap()
+    }
+
+    // (frames since midnight, reverse, speed estimate) of every decoded frame
+    fn decode(audio: &[u8]) -> Vec<(i64, bool, Option<f64>)> {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut estimator = SpeedEstimator::new(&SpeedEstimatorConfig::default());
+        let mut frames = Vec::new();
+        for (i, block) in audio.chunks(1024).enumerate() {
+            decoder.write(block, (i * 1024) as i64);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                let ratio = estimator.push(&frame).map(|estimate| estimate.ratio);
+                frames.push((
+                    timecode.to_frames(FrameRate::FPS_25),
+                    frame.reverse(),
+                    ratio,
+                ));
+            }
+        }
+        frames
+    }
+
+    fn assert_steps(frames: &[(i64, bool, Option<f64>)], step: i64) {
+        for pair in frames.windows(2) {
+            assert_eq!(pair[1].0 - pair[0].0, step, "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn test_shuttle_constant() {
+        let audio = shuttle().render(2.0, |_| 0.5).unwrap();
+        let frames = decode(&audio);
+        assert!(frames.len() >= 23);
+        assert_steps(&frames, 1);
+        let ratio = frames.last().unwrap().2.unwrap();
+        assert!((ratio - 0.5).abs() < 0.01, "{ratio}");
+
+        let audio = shuttle().render(1.0, |_| -1.0).unwrap();
+        let frames = decode(&audio);
+        assert!(frames.len() >= 23);
+        assert!(frames.iter().all(|(_, reverse, _)| *reverse));
+        assert_steps(&frames, -1);
+    }
+
+    #[test]
+    fn test_shuttle_ramp() {
+        let audio = shuttle().render(2.0, |t| t).unwrap();
+        let frames = decode(&audio);
+        // The decoder may need a few of the very slow frames to catch on
+        assert!(frames.windows(2).all(|pair| pair[1].0 > pair[0].0));
+        assert_steps(&frames[frames.len() / 2..], 1);
+        let ratio = frames.last().unwrap().2.unwrap();
+        assert!(ratio > 1.5 && ratio <= 2.0, "{ratio}");
+    }
+
+    #[test]
+    fn test_shuttle_direction_change() {
+        let mut shuttle = shuttle();
+        let mut audio = shuttle.render(0.4, |_| 1.0).unwrap();
+        let turn = shuttle.timecode().to_frames(FrameRate::FPS_25) - 1;
+        audio.extend(shuttle.render(0.4, |_| -1.0).unwrap());
+        let frames = decode(&audio);
+
+        // Up to the turn, then the same frames back down
+        let top = frames.iter().position(|(_, reverse, _)| *reverse).unwrap();
+        assert_steps(&frames[..top], 1);
+        assert_steps(&frames[top..], -1);
+        assert!(frames[top].0 >= turn - 1 && frames[top].0 <= turn);
+    }
+
+    #[test]
+    fn test_shuttle_invalid_speed() {
+        let mut shuttle = shuttle();
+        let mut out = Vec::new();
+        for speed in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(shuttle.encode_frame(speed, &mut out).is_err(), "{speed}");
+        }
+        assert!(out.is_empty());
+        assert_eq!(shuttle.elapsed(), 0.0);
+        assert!(shuttle
+            .render(1.0, |t| if t > 0.5 { f64::NAN } else { 1.0 })
+            .is_err());
+    }
+
+    #[test]
+    fn test_shuttle_config() {
+        let start = SMPTETimecode::default();
+        for (min_speed, max_speed) in [
+            (0.1, -1.0),
+            (0.1, f64::NAN),
+            (f64::NAN, 10.0),
+            (0.0, 10.0),
+            (0.01, 10.0),
+            (2.0, 1.0),
+        ] {
+            let config = ShuttleConfig {
+                min_speed,
+                max_speed,
+                ..Default::default()
+            };
+            assert!(
+                ShuttleGenerator::try_new(&config, &start).is_err(),
+                "{min_speed} {max_speed}"
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::stamp::{frame_boundary, unwrap_frames};
+
+#[derive(Debug, Copy, Clone)]
+pub struct SpeedEstimatorConfig {
+    pub sample_rate: f64,
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // Number of frame intervals the estimate is averaged over
+    pub window: usize,
+    // Largest jump in frames that still counts as the same take, anything above starts over
+    pub max_gap: i64,
+    // Jitter below which the estimate is reported as stable
+    pub stability: f64,
+}
+
+impl Default for SpeedEstimatorConfig {
+    fn default() -> Self {
+        SpeedEstimatorConfig {
+            sample_rate: 48_000.0,
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            window: 8,
+            max_gap: 10,
+            stability: 0.01,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpeedEstimate {
+    // Playback speed relative to the nominal frame rate, negative in reverse
+    pub ratio: f64,
+    pub direction: Direction,
+    // Relative standard deviation of the speed of the frames in the window
+    pub jitter: f64,
+    pub stable: bool,
+}
+
+// Estimates shuttle speed from the spacing of decoded frames compared to the nominal
+// samples per frame
+#[derive(Debug)]
+pub struct SpeedEstimator {
+    config: SpeedEstimatorConfig,
+    // Frames since midnight (not wrapped) and boundary of the last frame
+    last: Option<(i64, f64)>,
+    // (frames advanced, samples it took) between consecutive frames
+    intervals: VecDeque<(i64, f64)>,
+}
+
+impl SpeedEstimator {
+    pub fn new(config: &SpeedEstimatorConfig) -> Self {
+        SpeedEstimator {
+            config: *config,
+            last: None,
+            intervals: VecDeque::with_capacity(config.window.max(1)),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.intervals.clear();
+    }
+
+    pub fn push(&mut self, frame: &LTCFrameExt) -> Option<SpeedEstimate> {
+        let boundary = frame_boundary(frame, 0.0);
+        let timecode = frame.ltc().to_timecode(self.config.flags);
+        let mut frames = timecode.to_frames(self.config.rate);
+
+        if let Some((last, previous)) = self.last {
+            frames = unwrap_frames(frames, last, self.config.rate);
+            let advanced = frames - last;
+            let samples = boundary - previous;
+            if advanced == 0 || advanced.abs() > self.config.max_gap || samples <= 0.0 {
+                self.intervals.clear();
+            } else {
+                if self.intervals.len() >= self.config.window.max(1) {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back((advanced, samples));
+            }
+        }
+        self.last = Some((frames, boundary));
+
+        self.estimate()
+    }
+
+    pub fn estimate(&self) -> Option<SpeedEstimate> {
+        if self.intervals.is_empty() {
+            return None;
+        }
+        let nominal = self.config.rate.samples_per_frame(self.config.sample_rate);
+
+        // Averaging over the whole window keeps the quantization of each boundary out
+        let (frames, samples) = self
+            .intervals
+            .iter()
+            .fold((0, 0.0), |(f, s), &(advanced, samples)| {
+                (f + advanced, s + samples)
+            });
+        let ratio = frames as f64 * nominal / samples;
+
+        let n = self.intervals.len() as f64;
+        let variance = self
+            .intervals
+            .iter()
+            .map(|&(advanced, samples)| {
+                let deviation = advanced as f64 * nominal / samples - ratio;
+                deviation * deviation
+            })
+            .sum::<f64>()
+            / n;
+        let jitter = variance.sqrt() / ratio.abs();
+
+        Some(SpeedEstimate {
+            ratio,
+            direction: if ratio < 0.0 {
+                Direction::Reverse
+            } else {
+                Direction::Forward
+            },
+            jitter,
+            stable: self.intervals.len() > 1 && jitter < self.config.stability,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::encoder::{LTCEncoder, LTCEncoderConfig};
+    use crate::api::{SMPTETimecode, Timezone};
+
+    // Encodes LTC at a speed by pretending the sample rate is different
+    fn encode(frame: u8, speed: f64, count: usize) -> Vec<u8> {
+        let config = LTCEncoderConfig {
+            sample_rate: 48_000.0 / speed.abs(),
+            ..Default::default()
+        };
+        let mut encoder = LTCEncoder::try_new(&config).unwrap();
+        encoder.set_timecode(&SMPTETimecode::new(
+            Timezone::default(),
+            0,
+            0,
+            0,
+            1,
+            0,
+            0,
+            frame,
+        ));
+        let mut audio = Vec::new();
+        for _ in 0..count {
+            if speed < 0.0 {
+                encoder.encode_reversed_frame();
+            } else {
+                encoder.encode_frame();
+            }
+            let (buf, len) = encoder.get_buf_ref(true);
+            audio.extend_from_slice(&buf[..len]);
+            if speed < 0.0 {
+                encoder.dec_timecode().unwrap();
+            } else {
+                encoder.inc_timecode().unwrap();
+            }
+        }
+        audio
+    }
+
+    fn estimate(audio: &[u8]) -> SpeedEstimate {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut estimator = SpeedEstimator::new(&SpeedEstimatorConfig::default());
+        let mut estimate = None;
+        for (i, block) in audio.chunks(1024).enumerate() {
+            decoder.write(block, (i * 1024) as i64);
+            while let Some(frame) = decoder.read() {
+                estimate = estimator.push(&frame).or(estimate);
+            }
+        }
+        estimate.unwrap()
+    }
+
+    #[test]
+    fn test_speed_forward() {
+        for speed in [1.0, 1.25, 0.5] {
+            let estimate = estimate(&encode(0, speed, 40));
+            assert!((estimate.ratio - speed).abs() < 0.005, "{estimate:?}");
+            assert_eq!(estimate.direction, Direction::Forward);
+            assert!(estimate.stable, "{estimate:?}");
+        }
+    }
+
+    #[test]
+    fn test_speed_reverse() {
+        let estimate = estimate(&encode(0, -1.0, 40));
+        assert!((estimate.ratio + 1.0).abs() < 0.005, "{estimate:?}");
+        assert_eq!(estimate.direction, Direction::Reverse);
+    }
+
+    #[test]
+    fn test_speed_shuttle() {
+        // Speeding up from 1x to 2x is not stable until it settles
+        let mut audio = encode(0, 1.0, 20);
+        audio.extend(encode(20, 2.0, 4));
+        let estimate = estimate(&audio);
+        assert!(estimate.ratio > 1.0 && estimate.ratio < 2.0);
+        assert!(!estimate.stable);
+    }
+}
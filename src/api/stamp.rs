@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use super::consts::LtcBgFlags;
+use super::frame::LTCFrameExt;
+use super::rate::FrameRate;
+use super::SMPTETimecode;
+
+#[derive(Debug, Copy, Clone)]
+pub struct FrameStamperConfig {
+    pub sample_rate: f64,
+    pub rate: FrameRate,
+    pub flags: LtcBgFlags,
+    // Samples between a frame ending in the stream and the decoder reporting its end
+    pub latency: f64,
+    // Number of frames the frame boundary timeline is fitted over
+    pub window: usize,
+}
+
+impl Default for FrameStamperConfig {
+    fn default() -> Self {
+        FrameStamperConfig {
+            sample_rate: 48_000.0,
+            rate: FrameRate::default(),
+            flags: LtcBgFlags::default(),
+            latency: 0.0,
+            window: 32,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StampedFrame {
+    pub frame: LTCFrameExt,
+    pub timecode: SMPTETimecode,
+    // Sub-sample stream position of the frame boundary (the leading edge of bit 0)
+    pub start: f64,
+    // Measured frame duration in samples, negative when the stream plays in reverse
+    pub samples_per_frame: f64,
+}
+
+// Fits a straight line through the boundaries of consecutive decoded frames. Each boundary is
+// only known to the sample, the fit averages the quantization out over the window.
+#[derive(Debug)]
+pub struct FrameStamper {
+    config: FrameStamperConfig,
+    // (frames since midnight without wrapping, measured boundary)
+    history: VecDeque<(i64, f64)>,
+    // boundary = intercept + slope * (frames - origin)
+    fit: Option<(i64, f64, f64)>,
+}
+
+impl FrameStamper {
+    pub fn new(config: &FrameStamperConfig) -> Self {
+        FrameStamper {
+            config: *config,
+            history: VecDeque::with_capacity(config.window.max(1)),
+            fit: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.fit = None;
+    }
+
+    pub fn push(&mut self, frame: LTCFrameExt) -> StampedFrame {
+        let rate = self.config.rate;
+        let timecode = frame.ltc().to_timecode(self.config.flags);
+        let measured = frame_boundary(&frame, self.config.latency);
+
+        let mut frames = timecode.to_frames(rate);
+        if let Some(&(last, _)) = self.history.back() {
+            frames = unwrap_frames(frames, last, rate);
+        }
+
+        // A boundary that is off by more than half a frame means the timeline jumped
+        let nominal = rate.samples_per_frame(self.config.sample_rate);
+        if let Some(predicted) = self.predict(frames) {
+            if (predicted - measured).abs() > nominal / 2.0 {
+                self.reset();
+            }
+        }
+
+        if self.history.len() >= self.config.window.max(1) {
+            self.history.pop_front();
+        }
+        self.history.push_back((frames, measured));
+        self.refit(if frame.reverse() { -nominal } else { nominal });
+
+        StampedFrame {
+            start: self.predict(frames).unwrap_or(measured),
+            samples_per_frame: self.fit.map_or(nominal, |(_, _, slope)| slope),
+            frame,
+            timecode,
+        }
+    }
+
+    // The timecode running at a stream position and how far into that frame (0..1) it is
+    pub fn timecode_at(&self, sample: f64) -> Option<(SMPTETimecode, f64)> {
+        let position = self.position_at(sample)?;
+        let frames = position.floor();
+        Some((
+            SMPTETimecode::from_frames(frames as i64, self.config.rate),
+            position - frames,
+        ))
+    }
+
+    // Frames since midnight at a stream position, with the fraction and without wrapping at
+    // midnight, so that it keeps counting across it
+    pub fn position_at(&self, sample: f64) -> Option<f64> {
+        let (origin, intercept, slope) = self.fit?;
+        Some(origin as f64 + (sample - intercept) / slope)
+    }
+
+    // The inverse of `position_at`
+    pub fn sample_at(&self, position: f64) -> Option<f64> {
+        let (origin, intercept, slope) = self.fit?;
+        Some(intercept + slope * (position - origin as f64))
+    }
+
+    // The stream position of the boundary of a frame, extrapolated from the current fit
+    pub fn start_of(&self, timecode: &SMPTETimecode) -> Option<f64> {
+        let (origin, _, _) = self.fit?;
+        let frames = unwrap_frames(
+            timecode.to_frames(self.config.rate),
+            origin,
+            self.config.rate,
+        );
+        self.predict(frames)
+    }
+
+    fn predict(&self, frames: i64) -> Option<f64> {
+        self.fit
+            .map(|(origin, intercept, slope)| intercept + slope * (frames - origin) as f64)
+    }
+
+    fn refit(&mut self, nominal: f64) {
+        let Some(&(origin, _)) = self.history.front() else {
+            self.fit = None;
+            return;
+        };
+        let n = self.history.len() as f64;
+        let (sum_x, sum_y) = self
+            .history
+            .iter()
+            .fold((0.0, 0.0), |(x, y), &(frames, start)| {
+                (x + (frames - origin) as f64, y + start)
+            });
+        let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+        let (sxx, sxy) = self
+            .history
+            .iter()
+            .fold((0.0, 0.0), |(xx, xy), &(frames, start)| {
+                let dx = (frames - origin) as f64 - mean_x;
+                (xx + dx * dx, xy + dx * (start - mean_y))
+            });
+        let slope = if sxx > 0.0 { sxy / sxx } else { nominal };
+        self.fit = Some((origin, mean_y - slope * mean_x, slope));
+    }
+}
+
+// Stream position of the leading edge of bit 0 of a decoded frame
+pub(crate) fn frame_boundary(frame: &LTCFrameExt, latency: f64) -> f64 {
+    // The tics add up to the length of the frame, which gives the start a fractional part
+    let length: f64 = frame.biphase_tics().iter().map(|&tics| tics as f64).sum();
+    let end = (frame.off_end() + 1) as f64 - latency;
+    // Played backwards bit 0 is the last bit to arrive
+    if frame.reverse() {
+        end
+    } else {
+        end - length
+    }
+}
+
+// Moves `frames` by whole days so that it lands as close as possible to `reference`
+pub(crate) fn unwrap_frames(frames: i64, reference: i64, rate: FrameRate) -> i64 {
+    let day = rate.frames_per_day();
+    frames + (reference - frames + day / 2).div_euclid(day) * day
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::encoder::{LTCEncoder, LTCEncoderConfig};
+    use crate::api::Timezone;
+
+    // Encodes `count` frames and returns the audio and the exact boundary of every frame
+    fn encode(start: &SMPTETimecode, count: usize) -> (Vec<u8>, Vec<usize>) {
+        let mut encoder = LTCEncoder::try_new(&LTCEncoderConfig::default()).unwrap();
+        encoder.set_timecode(start);
+        let mut audio = Vec::new();
+        let mut boundaries = Vec::new();
+        for _ in 0..count {
+            boundaries.push(audio.len());
+            encoder.encode_frame();
+            let (buf, len) = encoder.get_buf_ref(true);
+            audio.extend_from_slice(&buf[..len]);
+            encoder.inc_timecode().unwrap();
+        }
+        (audio, boundaries)
+    }
+
+    fn decode(audio: &[u8], stamper: &mut FrameStamper) -> Vec<StampedFrame> {
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut stamped = Vec::new();
+        for (i, chunk) in audio.chunks(256).enumerate() {
+            decoder.write(chunk, (i * 256) as i64);
+            while let Some(frame) = decoder.read() {
+                stamped.push(stamper.push(frame));
+            }
+        }
+        stamped
+    }
+
+    #[test]
+    fn test_stamper_boundaries() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let (audio, boundaries) = encode(&start, 50);
+        let mut stamper = FrameStamper::new(&FrameStamperConfig::default());
+        let stamped = decode(&audio, &mut stamper);
+        assert!(stamped.len() > 40);
+
+        // The latency of the decoder is constant, only the spacing has to be exact
+        let first = &stamped[stamped.len() - 20];
+        let first_index = first.timecode.to_frames(FrameRate::FPS_25) as usize
+            - start.to_frames(FrameRate::FPS_25) as usize;
+        for frame in &stamped[stamped.len() - 20..] {
+            let index = frame.timecode.to_frames(FrameRate::FPS_25) as usize
+                - start.to_frames(FrameRate::FPS_25) as usize;
+            let expected = (boundaries[index] - boundaries[first_index]) as f64;
+            let measured = frame.start - first.start;
+            assert!((expected - measured).abs() < 0.5, "{expected} {measured}");
+        }
+        assert!((stamped.last().unwrap().samples_per_frame - 1920.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_stamper_timecode_at() {
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 10, 0, 0, 0);
+        let (audio, _) = encode(&start, 30);
+        let mut stamper = FrameStamper::new(&FrameStamperConfig::default());
+        let stamped = decode(&audio, &mut stamper);
+        let last = stamped.last().unwrap();
+
+        let (timecode, fraction) = stamper
+            .timecode_at(last.start + last.samples_per_frame * 2.5)
+            .unwrap();
+        assert_eq!(
+            timecode.to_frames(FrameRate::FPS_25),
+            last.timecode.to_frames(FrameRate::FPS_25) + 2
+        );
+        assert!((fraction - 0.5).abs() < 0.01);
+        assert!((stamper.start_of(&last.timecode).unwrap() - last.start).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,322 @@
+use super::consts::SampleType;
+use super::generator::{LTCGenerator, LTCGeneratorConfig};
+use super::hfr::hfr_generator;
+use super::sample::Sample;
+use super::SMPTETimecode;
+use crate::error::LTCEncoderError;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LTCTrackConfig {
+    pub generator: LTCGeneratorConfig,
+    // Seconds of LTC before the program start and after its end, rounded to whole frames
+    pub pre_roll: f64,
+    pub post_roll: f64,
+    // Seconds of silence before the first and after the last frame
+    pub leading_silence: f64,
+    pub trailing_silence: f64,
+    // Seconds at the end of the post-roll over which the LTC ramps down to silence. Zero
+    // stops hard on the last edge.
+    pub fade_out: f64,
+}
+
+impl Default for LTCTrackConfig {
+    fn default() -> Self {
+        LTCTrackConfig {
+            generator: LTCGeneratorConfig::default(),
+            pre_roll: 0.0,
+            post_roll: 0.0,
+            leading_silence: 0.0,
+            trailing_silence: 0.0,
+            fade_out: 0.0,
+        }
+    }
+}
+
+// A finite timecode track: silence, pre-roll, the program, post-roll, a clean end on a full
+// frame and silence again. Rates above 30 fps go through `hfr_generator`.
+#[derive(Debug)]
+pub struct LTCTrack {
+    generator: LTCGenerator,
+    // Sample counts of the silence and of the LTC
+    leading: usize,
+    ltc: usize,
+    trailing: usize,
+    // Sample count of the ramp at the end of the LTC, never longer than the LTC itself
+    fade: usize,
+    // Length of what end_encode added after the last frame, known once the LTC runs out. The
+    // samples stay in the encoder buffer.
+    tail: Option<usize>,
+    // Samples handed out so far
+    position: usize,
+}
+
+impl LTCTrack {
+    // `duration` is the length of the program in seconds, rounded up to whole frames
+    pub fn try_new(
+        config: &LTCTrackConfig,
+        start: &SMPTETimecode,
+        duration: f64,
+    ) -> Result<Self, LTCEncoderError> {
+        let valid = |seconds: f64| seconds.is_finite() && seconds >= 0.0;
+        let lengths = [
+            config.pre_roll,
+            config.post_roll,
+            config.leading_silence,
+            config.trailing_silence,
+            config.fade_out,
+            duration,
+        ];
+        if !lengths.into_iter().all(valid) {
+            return Err(LTCEncoderError::ConfigError);
+        }
+
+        let rate = config.generator.rate;
+        let sample_rate = config.generator.sample_rate;
+        let frames = |seconds: f64| (seconds * rate.fps()).round() as i64;
+
+        let pre_roll = frames(config.pre_roll);
+        let program = (duration * rate.fps()).ceil() as i64;
+        let mut total = pre_roll + program + frames(config.post_roll);
+
+        let first = start.with_frames(start.to_frames(rate) - pre_roll, rate);
+        let generator = if rate.is_high_frame_rate() {
+            // Every LTC frame carries a pair, so the LTC ends on a whole pair
+            total += total % 2;
+            hfr_generator(&config.generator, &first)?
+        } else {
+            LTCGenerator::try_new(&config.generator, &first)?
+        };
+        let ltc = rate.frame_start(total, sample_rate) as usize;
+        Ok(LTCTrack {
+            generator,
+            leading: (config.leading_silence * sample_rate).round() as usize,
+            ltc,
+            trailing: (config.trailing_silence * sample_rate).round() as usize,
+            fade: ((config.fade_out * sample_rate).round() as usize).min(ltc),
+            tail: None,
+            position: 0,
+        })
+    }
+
+    pub fn set_user_bits(&mut self, data: u32) {
+        self.generator.set_user_bits(data);
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tail
+            .is_some_and(|tail| self.position >= self.leading + self.ltc + tail + self.trailing)
+    }
+
+    // Returns how many samples were written, less than `out.len()` only at the end of the track
+    pub fn fill<S: Sample>(&mut self, out: &mut [S]) -> Result<usize, LTCEncoderError> {
+        let silence = S::from_ltc(128);
+        let mut written = 0;
+        while written < out.len() && !self.is_finished() {
+            let rest = &mut out[written..];
+            let n = if self.position < self.leading {
+                let n = rest.len().min(self.leading - self.position);
+                rest[..n].fill(silence);
+                n
+            } else if self.position < self.leading + self.ltc - self.fade {
+                let n = rest
+                    .len()
+                    .min(self.leading + self.ltc - self.fade - self.position);
+                self.generator.fill(&mut rest[..n])?;
+                n
+            } else if self.position < self.leading + self.ltc {
+                // The ramp goes through 8 bit samples, a block at a time
+                let mut block: [SampleType; 1024] = [128; 1024];
+                let remaining = self.leading + self.ltc - self.position;
+                let n = rest.len().min(remaining).min(block.len());
+                self.generator.fill(&mut block[..n])?;
+                for (i, (out, &sample)) in rest.iter_mut().zip(&block[..n]).enumerate() {
+                    let gain = (remaining - i) as f64 / self.fade as f64;
+                    let faded = 128.0 + (sample as f64 - 128.0) * gain;
+                    *out = S::from_ltc(faded.round() as SampleType);
+                }
+                n
+            } else {
+                if self.tail.is_none() {
+                    self.tail = Some(self.generator.finish()?.len());
+                }
+                let offset = self.position - self.leading - self.ltc;
+                let tail = self.generator.buffered();
+                if offset < tail.len() && self.fade > 0 {
+                    // The ramp already reached silence
+                    let n = rest.len().min(tail.len() - offset);
+                    rest[..n].fill(silence);
+                    n
+                } else if offset < tail.len() {
+                    let n = rest.len().min(tail.len() - offset);
+                    for (out, &sample) in rest.iter_mut().zip(&tail[offset..offset + n]) {
+                        *out = S::from_ltc(sample);
+                    }
+                    n
+                } else {
+                    let n = rest.len().min(tail.len() + self.trailing - offset);
+                    rest[..n].fill(silence);
+                    n
+                }
+            };
+            written += n;
+            self.position += n;
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::consts::LtcBgFlags;
+    use crate::api::decoder::{LTCDecoder, LTCDecoderConfig};
+    use crate::api::hfr::hfr_frames;
+    use crate::api::rate::FrameRate;
+    use crate::api::Timezone;
+
+    #[test]
+    fn test_track() {
+        let config = LTCTrackConfig {
+            pre_roll: 0.2,
+            post_roll: 0.2,
+            leading_silence: 0.1,
+            trailing_silence: 0.1,
+            ..Default::default()
+        };
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 1, 0, 0, 0);
+        let mut track = LTCTrack::try_new(&config, &start, 1.0).unwrap();
+
+        let mut audio = Vec::new();
+        let mut block = [0u8; 1000];
+        loop {
+            let n = track.fill(&mut block).unwrap();
+            audio.extend_from_slice(&block[..n]);
+            if n < block.len() {
+                break;
+            }
+        }
+        assert!(track.is_finished());
+        assert_eq!(track.fill(&mut block).unwrap(), 0);
+
+        // Silence, 35 frames and the end of the last one, silence
+        let ltc = 35 * 1920;
+        assert!(audio.len() > 4800 + ltc + 4800);
+        assert!(audio[..4800].iter().all(|&sample| sample == 128));
+        assert!(audio[audio.len() - 4800..]
+            .iter()
+            .all(|&sample| sample == 128));
+        assert!(audio[4800..4800 + ltc].iter().any(|&sample| sample != 128));
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut frames = Vec::new();
+        for (i, block) in audio.chunks(1920).enumerate() {
+            decoder.write(block, (i * 1920) as i64);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                frames.push(timecode.to_frames(FrameRate::FPS_25));
+            }
+        }
+        let start = start.to_frames(FrameRate::FPS_25);
+        assert!(frames.len() >= 33);
+        assert!(frames[0] >= start - 5 && frames[0] < start);
+        assert_eq!(*frames.last().unwrap(), start + 29);
+    }
+
+    #[test]
+    fn test_track_high_frame_rate() {
+        let rate = FrameRate::FPS_50;
+        let config = LTCTrackConfig {
+            generator: LTCGeneratorConfig {
+                rate,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // 25 frames from the second frame of a pair, rounded up to 13 pairs
+        let start: SMPTETimecode = "01:00:00:01".parse().unwrap();
+        let mut track = LTCTrack::try_new(&config, &start, 0.5).unwrap();
+        let mut audio = vec![0u8; 48_000];
+        let n = track.fill(&mut audio).unwrap();
+        assert!(track.is_finished());
+        assert!(n >= 26 * 960);
+
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio[..n], 0);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read() {
+            frames.extend(hfr_frames(&frame, rate, LtcBgFlags::default()).unwrap());
+        }
+        let labels: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.timecode.to_time_string(false))
+            .collect();
+        assert_eq!(labels.first().unwrap(), "01:00:00:01");
+        assert_eq!(labels.last().unwrap(), "01:00:00:26");
+    }
+
+    #[test]
+    fn test_track_fade_out() {
+        let config = LTCTrackConfig {
+            post_roll: 0.2,
+            trailing_silence: 0.1,
+            fade_out: 0.1,
+            ..Default::default()
+        };
+        let start = SMPTETimecode::new(Timezone::default(), 0, 0, 0, 1, 0, 0, 0);
+        let mut track = LTCTrack::try_new(&config, &start, 1.0).unwrap();
+        let mut audio = vec![0u8; 96_000];
+        let n = track.fill(&mut audio).unwrap();
+        assert!(track.is_finished());
+
+        // Full level until the ramp, down to silence at the end of the 30 frames
+        let ltc = 30 * 1920;
+        let level = |samples: &[u8]| samples.iter().map(|&s| s.abs_diff(128)).max().unwrap();
+        let full = level(&audio[..ltc - 4800]);
+        assert_eq!(level(&audio[ltc - 4800..ltc - 4320]), full);
+        assert!(level(&audio[ltc - 1920..ltc - 960]) < full / 2);
+        assert!(level(&audio[ltc - 480..ltc]) < full / 8);
+        assert!(audio[ltc..n].iter().all(|&sample| sample == 128));
+
+        // The faded frames still decode
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        let mut frames = Vec::new();
+        for (i, block) in audio[..n].chunks(1920).enumerate() {
+            decoder.write(block, (i * 1920) as i64);
+            while let Some(frame) = decoder.read() {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                frames.push(timecode.to_frames(FrameRate::FPS_25));
+            }
+        }
+        assert!(frames.len() >= 28);
+    }
+
+    #[test]
+    fn test_track_config() {
+        let start = SMPTETimecode::default();
+        for config in [
+            LTCTrackConfig {
+                pre_roll: -1.0,
+                ..Default::default()
+            },
+            LTCTrackConfig {
+                post_roll: f64::NAN,
+                ..Default::default()
+            },
+            LTCTrackConfig {
+                trailing_silence: f64::INFINITY,
+                ..Default::default()
+            },
+            LTCTrackConfig {
+                fade_out: -0.1,
+                ..Default::default()
+            },
+        ] {
+            assert!(LTCTrack::try_new(&config, &start, 1.0).is_err());
+        }
+        assert!(LTCTrack::try_new(&LTCTrackConfig::default(), &start, -1.0).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+// Audio input shared by the command line tools
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Wav,
+    Raw,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct InputArgs {
+    #[arg(help = "WAV or raw file, - reads raw PCM from stdin")]
+    pub input: PathBuf,
+    #[arg(
+        long,
+        help = "Input format, by default WAV for .wav files and raw otherwise"
+    )]
+    pub format: Option<InputFormat>,
+    #[arg(
+        short = 'r',
+        long,
+        default_value_t = 48_000,
+        help = "Sample rate of raw input"
+    )]
+    pub sample_rate: u32,
+    #[arg(
+        short,
+        long,
+        default_value_t = 8,
+        help = "Bits per sample of raw input: 8 (unsigned), 16, 24 or 32 (float)"
+    )]
+    pub bits: u16,
+    #[arg(long, default_value_t = 1, help = "Interleaved channels of raw input")]
+    pub channels: u16,
+}
+
+// The format given or else the one the file name suggests, stdin is always raw
+fn input_format(args: &InputArgs) -> InputFormat {
+    let stdin = args.input.as_os_str() == "-";
+    args.format.unwrap_or_else(|| {
+        match args
+            .input
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some(extension) if !stdin && extension.eq_ignore_ascii_case("wav") => InputFormat::Wav,
+            _ => InputFormat::Raw,
+        }
+    })
+}
+
+enum Source {
+    Wav(hound::WavReader<BufReader<File>>),
+    Raw(Box<dyn Read>),
+}
+
+// Reads interleaved samples of all channels as floats between -1 and 1
+pub struct AudioInput {
+    source: Source,
+    sample_rate: u32,
+    channels: u16,
+    bits: u16,
+    float: bool,
+    bytes: Vec<u8>,
+}
+
+impl AudioInput {
+    pub fn open(args: &InputArgs) -> Result<Self, Box<dyn Error>> {
+        let stdin = args.input.as_os_str() == "-";
+        match input_format(args) {
+            InputFormat::Wav if stdin => Err("WAV can't be read from stdin".into()),
+            InputFormat::Wav => {
+                let reader = hound::WavReader::open(&args.input)?;
+                let spec = reader.spec();
+                Ok(AudioInput {
+                    source: Source::Wav(reader),
+                    sample_rate: spec.sample_rate,
+                    channels: spec.channels,
+                    bits: spec.bits_per_sample,
+                    float: spec.sample_format == hound::SampleFormat::Float,
+                    bytes: Vec::new(),
+                })
+            }
+            InputFormat::Raw if stdin => AudioInput::raw(Box::new(io::stdin().lock()), args),
+            InputFormat::Raw => {
+                AudioInput::raw(Box::new(BufReader::new(File::open(&args.input)?)), args)
+            }
+        }
+    }
+
+    fn raw(reader: Box<dyn Read>, args: &InputArgs) -> Result<Self, Box<dyn Error>> {
+        if ![8, 16, 24, 32].contains(&args.bits) {
+            return Err("bits must be 8, 16, 24 or 32".into());
+        }
+        if args.channels == 0 {
+            return Err("there has to be at least one channel".into());
+        }
+        Ok(AudioInput {
+            source: Source::Raw(reader),
+            sample_rate: args.sample_rate,
+            channels: args.channels,
+            bits: args.bits,
+            float: args.bits == 32,
+            bytes: Vec::new(),
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    // Fills `out` with whole interleaved sample frames and returns how many it read, 0 at the end
+    pub fn read(&mut self, out: &mut [f32]) -> Result<usize, Box<dyn Error>> {
+        let channels = self.channels as usize;
+        let len = out.len() / channels * channels;
+        let scale = (1u32 << (self.bits - 1)) as f32;
+
+        let n = match &mut self.source {
+            Source::Wav(reader) if self.float => {
+                let mut n = 0;
+                for (out, sample) in out[..len].iter_mut().zip(reader.samples::<f32>()) {
+                    *out = sample?;
+                    n += 1;
+                }
+                n
+            }
+            Source::Wav(reader) => {
+                let mut n = 0;
+                for (out, sample) in out[..len].iter_mut().zip(reader.samples::<i32>()) {
+                    *out = sample? as f32 / scale;
+                    n += 1;
+                }
+                n
+            }
+            Source::Raw(reader) => {
+                let width = self.bits as usize / 8;
+                self.bytes.resize(len * width, 0);
+                let mut filled = 0;
+                while filled < self.bytes.len() {
+                    match reader.read(&mut self.bytes[filled..]) {
+                        Ok(0) => break,
+                        Ok(n) => filled += n,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                for (out, bytes) in out.iter_mut().zip(self.bytes[..filled].chunks_exact(width)) {
+                    *out = match bytes {
+                        [byte] => (*byte as f32 - 128.0) / scale,
+                        [a, b] => i16::from_le_bytes([*a, *b]) as f32 / scale,
+                        [a, b, c] => (i32::from_le_bytes([0, *a, *b, *c]) >> 8) as f32 / scale,
+                        _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    };
+                }
+                filled / width
+            }
+        };
+        Ok(n / channels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str, format: Option<InputFormat>, bits: u16, channels: u16) -> InputArgs {
+        InputArgs {
+            input: input.into(),
+            format,
+            sample_rate: 48_000,
+            bits,
+            channels,
+        }
+    }
+
+    fn read_raw(bytes: &[u8], bits: u16, channels: u16) -> Vec<f32> {
+        let args = args("-", None, bits, channels);
+        let mut input = AudioInput::raw(Box::new(io::Cursor::new(bytes.to_vec())), &args).unwrap();
+        let mut out = [0.0; 16];
+        let n = input.read(&mut out).unwrap();
+        out[..n * channels as usize].to_vec()
+    }
+
+    #[test]
+    fn test_raw_formats() {
+        // 8 bit is unsigned with 128 as silence
+        assert_eq!(read_raw(&[0, 128, 255], 8, 1), [-1.0, 0.0, 127.0 / 128.0]);
+
+        // 16 bit is signed little endian
+        let scale = 32_768.0;
+        assert_eq!(
+            read_raw(&[0x00, 0x80, 0x00, 0x00, 0x01, 0x00, 0xFF, 0x7F], 16, 1),
+            [-1.0, 0.0, 1.0 / scale, 32_767.0 / scale]
+        );
+
+        // 24 bit is packed into 3 bytes and sign extended
+        let scale = 8_388_608.0;
+        assert_eq!(
+            read_raw(
+                &[0x00, 0x00, 0x80, 0xFF, 0xFF, 0xFF, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0x7F],
+                24,
+                1
+            ),
+            [-1.0, -1.0 / scale, 1.0 / scale, 8_388_607.0 / scale]
+        );
+
+        // 32 bit is little endian float
+        let mut bytes = Vec::new();
+        for sample in [0.5f32, -0.25, 1.0] {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        assert_eq!(read_raw(&bytes, 32, 1), [0.5, -0.25, 1.0]);
+    }
+
+    #[test]
+    fn test_raw_channels() {
+        // Only whole sample frames are read, the half frame at the end is dropped
+        let bytes = [0x00, 0x80, 0x00, 0x00, 0xFF, 0x7F, 0x00, 0x40, 0x00];
+        let mut input =
+            AudioInput::raw(Box::new(io::Cursor::new(bytes)), &args("-", None, 16, 2)).unwrap();
+        let mut out = [0.0; 4];
+        assert_eq!(input.read(&mut out).unwrap(), 2);
+        assert_eq!(out, [-1.0, 0.0, 32_767.0 / 32_768.0, 0.5]);
+        assert_eq!(input.read(&mut out).unwrap(), 0);
+
+        for (bits, channels) in [(12, 1), (16, 0)] {
+            let args = args("-", None, bits, channels);
+            assert!(AudioInput::raw(Box::new(io::empty()), &args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_input_format() {
+        for (input, format, expected) in [
+            ("take.wav", None, InputFormat::Wav),
+            ("TAKE.WAV", None, InputFormat::Wav),
+            ("take.raw", None, InputFormat::Raw),
+            ("take", None, InputFormat::Raw),
+            ("-", None, InputFormat::Raw),
+            ("take.wav", Some(InputFormat::Raw), InputFormat::Raw),
+            ("take.pcm", Some(InputFormat::Wav), InputFormat::Wav),
+        ] {
+            assert_eq!(
+                input_format(&args(input, format, 8, 1)),
+                expected,
+                "{input}"
+            );
+        }
+        assert!(AudioInput::open(&args("-", Some(InputFormat::Wav), 8, 1)).is_err());
+    }
+
+    #[test]
+    fn test_wav() {
+        let path = std::env::temp_dir().join(format!("ltc-input-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for sample in [-8_388_608, 0, 4_194_304, -1] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        // The header wins over the raw options
+        let mut input = AudioInput::open(&args(path.to_str().unwrap(), None, 8, 1)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((input.sample_rate(), input.channels()), (44_100, 2));
+        let mut out = [0.0; 8];
+        assert_eq!(input.read(&mut out).unwrap(), 2);
+        assert_eq!(out[..4], [-1.0, 0.0, 0.5, -1.0 / 8_388_608.0]);
+    }
+}
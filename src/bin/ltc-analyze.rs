@@ -0,0 +1,558 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::exit;
+
+use clap::Parser;
+use libltc_rs::prelude::*;
+
+use common::{AudioInput, InputArgs};
+
+mod common;
+
+// Frames looked at before settling on a frame rate
+const DETECT_FRAMES: usize = 60;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "ltc-analyze",
+    version,
+    about = "Scan a file for LTC and print a QC report"
+)]
+struct Args {
+    #[command(flatten)]
+    input: InputArgs,
+    #[arg(short, long, help = "Frame rate to assume instead of detecting it")]
+    fps: Option<FrameRate>,
+}
+
+// The rate the LTC itself runs at, above 30 fps every LTC frame carries a frame pair
+fn ltc_rate(rate: FrameRate) -> FrameRate {
+    if rate.is_high_frame_rate() {
+        rate.pair_rate()
+    } else {
+        rate
+    }
+}
+
+fn decoder_config(fps: Option<FrameRate>, sample_rate: f64) -> LTCDecoderConfig {
+    LTCDecoderConfig {
+        apv: ltc_rate(fps.unwrap_or_default())
+            .samples_per_frame(sample_rate)
+            .round() as i32,
+        ..Default::default()
+    }
+}
+
+// Running min, max and mean
+#[derive(Debug, Default)]
+struct Stats {
+    count: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Stats {
+    fn push(&mut self, value: f64) {
+        if self.count == 0 || value < self.min {
+            self.min = value;
+        }
+        if self.count == 0 || value > self.max {
+            self.max = value;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.count.max(1) as f64
+    }
+}
+
+struct Channel {
+    decoder: LTCDecoder,
+    // Frames decoded before the frame rate is known
+    pending: Vec<LTCFrameExt>,
+    rate: Option<FrameRate>,
+    analyzer: Option<ContinuityAnalyzer>,
+    frames: usize,
+    reverse: usize,
+    invalid: usize,
+    first: Option<(SMPTETimecode, i64)>,
+    last: Option<(SMPTETimecode, i64)>,
+    events: Vec<Discontinuity>,
+    level: Stats,
+    jitter: Stats,
+}
+
+impl Channel {
+    fn new(config: &LTCDecoderConfig) -> Result<Self, LTCDecoderError> {
+        Ok(Channel {
+            decoder: LTCDecoder::try_new(config)?,
+            pending: Vec::new(),
+            rate: None,
+            analyzer: None,
+            frames: 0,
+            reverse: 0,
+            invalid: 0,
+            first: None,
+            last: None,
+            events: Vec::new(),
+            level: Stats::default(),
+            jitter: Stats::default(),
+        })
+    }
+
+    fn write(&mut self, samples: &[f32], position: i64, sample_rate: f64, fps: Option<FrameRate>) {
+        self.decoder.write_float(samples, position);
+        while let Some(frame) = self.decoder.read() {
+            if self.rate.is_some() {
+                self.process(&frame);
+            } else {
+                self.pending.push(frame);
+                if self.pending.len() >= DETECT_FRAMES {
+                    self.detect(sample_rate, fps);
+                }
+            }
+        }
+    }
+
+    fn detect(&mut self, sample_rate: f64, fps: Option<FrameRate>) {
+        let Some(rate) = fps.or_else(|| detect_rate(&self.pending, sample_rate)) else {
+            return;
+        };
+
+        self.rate = Some(rate);
+        self.analyzer = Some(ContinuityAnalyzer::new(&ContinuityConfig {
+            rate: ltc_rate(rate),
+            ..Default::default()
+        }));
+        for frame in std::mem::take(&mut self.pending) {
+            self.process(&frame);
+        }
+    }
+
+    fn process(&mut self, frame: &LTCFrameExt) {
+        let Some(rate) = self.rate else {
+            return;
+        };
+        let flags = LtcBgFlags::default();
+        let timecode = if rate.is_high_frame_rate() {
+            hfr_timecode(&frame.ltc(), rate, flags)
+                .unwrap_or_else(|_| frame.ltc().to_timecode(flags))
+        } else {
+            frame.ltc().to_timecode(flags)
+        };
+        let position = frame.off_start();
+        if self.first.is_none() {
+            self.first = Some((timecode.clone(), position));
+        }
+        self.last = Some((timecode, position));
+
+        self.frames += 1;
+        self.reverse += frame.reverse() as usize;
+        if !frame.ltc().validate_rate(rate).is_empty() {
+            self.invalid += 1;
+        }
+        if let Some(analyzer) = &mut self.analyzer {
+            self.events.extend(analyzer.push(frame));
+        }
+
+        self.level.push(frame.volume());
+        // How evenly the bits are spaced within the frame
+        let tics = frame.biphase_tics();
+        let mean = tics.iter().map(|&tic| tic as f64).sum::<f64>() / tics.len() as f64;
+        if mean > 0.0 {
+            let variance = tics
+                .iter()
+                .map(|&tic| (tic as f64 - mean).powi(2))
+                .sum::<f64>()
+                / tics.len() as f64;
+            self.jitter.push(variance.sqrt() / mean * 100.0);
+        }
+    }
+}
+
+// Frames per second going by the spacing of consecutive frames, None with less than two
+fn measured_fps(frames: &[LTCFrameExt], sample_rate: f64) -> Option<f64> {
+    let mut steps: Vec<f64> = frames
+        .windows(2)
+        .map(|pair| (pair[1].off_end() - pair[0].off_end()) as f64)
+        .collect();
+    steps.sort_by(f64::total_cmp);
+    let median = *steps.get(steps.len() / 2)?;
+    // Leave out the gaps where frames went missing
+    let mut spacing = Stats::default();
+    for &step in steps
+        .iter()
+        .filter(|&&step| (step - median).abs() < median * 0.1)
+    {
+        spacing.push(step);
+    }
+    (spacing.mean() > 0.0).then(|| sample_rate / spacing.mean())
+}
+
+// The spacing of the frames picks the timebase and whether it runs at x/1.001. The labels only
+// decide when the spacing is about as far from two timebases, or when there's a single frame.
+fn detect_rate(frames: &[LTCFrameExt], sample_rate: f64) -> Option<FrameRate> {
+    let flags = LtcBgFlags::default();
+    let top = frames
+        .iter()
+        .map(|frame| frame.ltc().to_timecode(flags).frame())
+        .max()?;
+    let labelled = match top {
+        0..=23 => 24,
+        24 => 25,
+        _ => 30,
+    };
+
+    let fps = measured_fps(frames, sample_rate);
+    let timebase = match fps {
+        Some(fps) => {
+            let distance = |timebase: u32| (fps / timebase as f64).ln().abs();
+            let mut timebases = [24, 25, 30];
+            timebases.sort_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+            if distance(timebases[1]) - distance(timebases[0]) < 0.01
+                && timebases[..2].contains(&labelled)
+            {
+                labelled
+            } else {
+                timebases[0]
+            }
+        }
+        None => labelled,
+    };
+
+    let drop_frame = timebase == 30 && frames.iter().any(|frame| frame.ltc().dfbit() == 1);
+    // Closer to x/1.001 than to the whole rate
+    let slow = fps.is_some_and(|fps| timebase as f64 - fps > timebase as f64 * 0.0005);
+    Some(if drop_frame || slow {
+        FrameRate::new(timebase * 1000, 1001, drop_frame)
+    } else {
+        FrameRate::new(timebase, 1, false)
+    })
+}
+
+fn kind(kind: DiscontinuityKind) -> &'static str {
+    match kind {
+        DiscontinuityKind::Jump => "jump",
+        DiscontinuityKind::Repeat => "repeat",
+        DiscontinuityKind::Backwards => "backwards",
+        DiscontinuityKind::Dropped => "dropped frames",
+        DiscontinuityKind::DropFrameViolation => "invalid drop frame label",
+        DiscontinuityKind::MidnightWrap => "midnight wrap",
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut input = AudioInput::open(&args.input)?;
+    let sample_rate = input.sample_rate() as f64;
+    let channels = input.channels() as usize;
+
+    let config = decoder_config(args.fps, sample_rate);
+    let mut decoders = (0..channels)
+        .map(|_| Channel::new(&config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut interleaved = vec![0.0; 1024 * channels];
+    let mut samples = vec![0.0; 1024];
+    let mut position = 0;
+    loop {
+        let n = input.read(&mut interleaved)?;
+        if n == 0 {
+            break;
+        }
+        for (channel, decoder) in decoders.iter_mut().enumerate() {
+            for (sample, frame) in samples.iter_mut().zip(interleaved.chunks_exact(channels)) {
+                *sample = frame[channel];
+            }
+            decoder.write(&samples[..n], position, sample_rate, args.fps);
+        }
+        position += n as i64;
+    }
+    for decoder in &mut decoders {
+        decoder.detect(sample_rate, args.fps);
+    }
+
+    report(
+        &mut io::stdout().lock(),
+        &args.input.input,
+        input.sample_rate(),
+        position,
+        &decoders,
+    )
+}
+
+fn report(
+    out: &mut impl Write,
+    path: &Path,
+    sample_rate: u32,
+    samples: i64,
+    channels: &[Channel],
+) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "File:            {}", path.display())?;
+    writeln!(
+        out,
+        "Audio:           {} Hz, {} channel(s), {:.3} s",
+        sample_rate,
+        channels.len(),
+        samples as f64 / sample_rate as f64
+    )?;
+
+    // The channel with the most frames carries the LTC
+    let (index, channel) = channels
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, channel)| channel.frames)
+        .ok_or("no audio channels")?;
+    let (Some(rate), Some((first, first_position)), Some((last, last_position))) =
+        (channel.rate, &channel.first, &channel.last)
+    else {
+        writeln!(out, "LTC channel:     none found")?;
+        return Ok(());
+    };
+    let frame_counts: Vec<_> = channels
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| format!("{}: {}", i + 1, channel.frames))
+        .collect();
+    writeln!(
+        out,
+        "LTC channel:     {} (frames per channel {})",
+        index + 1,
+        frame_counts.join(", ")
+    )?;
+
+    let drop_frame = rate.is_drop_frame();
+    // How long the LTC runs in the file, whichever way the labels go, in video frames
+    let samples_per_frame = ltc_rate(rate).samples_per_frame(sample_rate as f64);
+    let ltc_frames = ((last_position - first_position) as f64 / samples_per_frame).round() as i64;
+    let span = (ltc_frames + 1) * (rate.timebase() / ltc_rate(rate).timebase()) as i64;
+    writeln!(
+        out,
+        "Frame rate:      {} ({})",
+        rate,
+        if drop_frame {
+            "drop frame"
+        } else {
+            "non drop frame"
+        }
+    )?;
+    writeln!(
+        out,
+        "First timecode:  {} at sample {}",
+        first.to_time_string(drop_frame),
+        first_position
+    )?;
+    writeln!(
+        out,
+        "Last timecode:   {} at sample {}",
+        last.to_time_string(drop_frame),
+        last_position
+    )?;
+    writeln!(
+        out,
+        "LTC duration:    {} ({} frames, {:.3} s)",
+        SMPTETimecode::from_frames(span, rate).to_time_string(drop_frame),
+        span,
+        span as f64 / rate.fps()
+    )?;
+    writeln!(
+        out,
+        "Frames:          {} decoded, {} reverse, {} invalid",
+        channel.frames, channel.reverse, channel.invalid
+    )?;
+    writeln!(
+        out,
+        "Level:           {:.1} dBFS mean ({:.1} to {:.1})",
+        channel.level.mean(),
+        channel.level.min,
+        channel.level.max
+    )?;
+    writeln!(
+        out,
+        "Bit jitter:      {:.2} % mean, {:.2} % max",
+        channel.jitter.mean(),
+        channel.jitter.max
+    )?;
+    writeln!(out, "Discontinuities: {}", channel.events.len())?;
+    for event in &channel.events {
+        writeln!(
+            out,
+            "  sample {:>10}: {}, expected {}, got {}",
+            event.position,
+            kind(event.kind),
+            event.expected.to_time_string(drop_frame),
+            event.observed.to_time_string(drop_frame)
+        )?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A channel that has been through `seconds` of LTC from `start`
+    fn analyze(rate: FrameRate, start: &str, seconds: f64, fps: Option<FrameRate>) -> Channel {
+        let config = LTCTrackConfig {
+            generator: LTCGeneratorConfig {
+                rate,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut track = LTCTrack::try_new(&config, &start.parse().unwrap(), seconds).unwrap();
+        let mut channel = Channel::new(&decoder_config(fps, 48_000.0)).unwrap();
+        let mut block = [0.0f32; 1024];
+        let mut position = 0;
+        loop {
+            let n = track.fill(&mut block).unwrap();
+            channel.write(&block[..n], position, 48_000.0, fps);
+            position += n as i64;
+            if n < block.len() {
+                break;
+            }
+        }
+        channel.detect(48_000.0, fps);
+        channel
+    }
+
+    #[test]
+    fn test_detect_rate() {
+        for (rate, start) in [
+            (FrameRate::FPS_23_976, "01:00:00:00"),
+            (FrameRate::FPS_24, "01:00:00:00"),
+            (FrameRate::FPS_25, "01:00:00:00"),
+            (FrameRate::FPS_29_97, "01:00:00:00"),
+            (FrameRate::FPS_29_97_DF, "00:00:59;20"),
+            (FrameRate::FPS_30, "01:00:00:00"),
+        ] {
+            assert_eq!(analyze(rate, start, 3.0, None).rate, Some(rate), "{rate}");
+        }
+    }
+
+    #[test]
+    fn test_detect_short() {
+        // A fifth of a second never labels a frame past 6, the spacing still tells the rate
+        for rate in [
+            FrameRate::FPS_25,
+            FrameRate::FPS_30,
+            FrameRate::FPS_29_97_DF,
+        ] {
+            let channel = analyze(rate, "00:00:00:00", 0.2, None);
+            assert!(channel.frames > 1, "{rate}");
+            assert_eq!(channel.rate, Some(rate), "{rate}");
+        }
+
+        // Without two frames to measure only the labels are left
+        assert_eq!(measured_fps(&[], 48_000.0), None);
+        assert_eq!(detect_rate(&[], 48_000.0), None);
+    }
+
+    #[test]
+    fn test_detect_given_rate() {
+        let channel = analyze(
+            FrameRate::FPS_25,
+            "01:00:00:00",
+            1.0,
+            Some(FrameRate::FPS_24),
+        );
+        assert_eq!(channel.rate, Some(FrameRate::FPS_24));
+    }
+
+    #[test]
+    fn test_report_high_frame_rate() {
+        let fps = Some(FrameRate::FPS_50);
+        let channel = analyze(FrameRate::FPS_50, "01:00:00:00", 2.0, fps);
+        assert_eq!(channel.invalid, 0);
+        let lines = report_lines(&[channel]);
+        assert_eq!(lines[3], "Frame rate:      50 (non drop frame)");
+        assert!(lines[4].starts_with("First timecode:  01:00:00:00 at sample "));
+        assert!(
+            lines[5].starts_with("Last timecode:   01:00:01:4"),
+            "{}",
+            lines[5]
+        );
+        assert_eq!(
+            lines[6],
+            "LTC duration:    00:00:02:00 (100 frames, 2.000 s)"
+        );
+        assert_eq!(lines.last().unwrap(), "Discontinuities: 0");
+    }
+
+    fn report_lines(channels: &[Channel]) -> Vec<String> {
+        let mut out = Vec::new();
+        report(&mut out, Path::new("take.wav"), 48_000, 0, channels).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn test_report_reverse() {
+        // Ten seconds played backwards from 01:00:10:00
+        let start: SMPTETimecode = "01:00:10:00".parse().unwrap();
+        let mut shuttle = ShuttleGenerator::try_new(&ShuttleConfig::default(), &start).unwrap();
+        let audio: Vec<f32> = shuttle
+            .render(10.0, |_| -1.0)
+            .unwrap()
+            .into_iter()
+            .map(f32::from_ltc)
+            .collect();
+        let mut channel = Channel::new(&LTCDecoderConfig::default()).unwrap();
+        for (i, block) in audio.chunks(1024).enumerate() {
+            channel.write(block, (i * 1024) as i64, 48_000.0, None);
+        }
+        channel.detect(48_000.0, None);
+        assert!(channel.reverse > 240, "{}", channel.reverse);
+
+        let lines = report_lines(&[channel]);
+        let duration = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("LTC duration:    "))
+            .unwrap();
+        assert!(duration.starts_with("00:00:09:2"), "{duration}");
+    }
+
+    #[test]
+    fn test_report() {
+        let channels = [
+            Channel::new(&LTCDecoderConfig::default()).unwrap(),
+            analyze(FrameRate::FPS_25, "01:00:00:00", 2.0, None),
+        ];
+        let mut out = Vec::new();
+        report(&mut out, Path::new("take.wav"), 48_000, 96_000, &channels).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "File:            take.wav");
+        assert_eq!(lines[1], "Audio:           48000 Hz, 2 channel(s), 2.000 s");
+        assert!(lines[2].starts_with("LTC channel:     2 (frames per channel 1: 0, 2: "));
+        assert_eq!(lines[3], "Frame rate:      25 (non drop frame)");
+        assert!(lines[4].starts_with("First timecode:  01:00:00:00 at sample "));
+        assert!(
+            lines[5].starts_with("Last timecode:   01:00:01:2"),
+            "{}",
+            lines[5]
+        );
+        assert!(lines[7].ends_with(" 0 reverse, 0 invalid"), "{}", lines[7]);
+        assert_eq!(lines.last().unwrap(), &"Discontinuities: 0");
+
+        let mut out = Vec::new();
+        report(&mut out, Path::new("-"), 48_000, 0, &channels[..1]).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with("LTC channel:     none found\n"));
+    }
+}
@@ -0,0 +1,379 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{Parser, ValueEnum};
+use libltc_rs::prelude::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Wav,
+    Raw,
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "ltc-gen",
+    version,
+    about = "Generate LTC audio as WAV or raw PCM"
+)]
+struct Args {
+    #[arg(help = "Output file, - writes raw PCM to stdout")]
+    output: PathBuf,
+    #[arg(short, long, default_value = "00:00:00:00", help = "Start timecode")]
+    start: String,
+    #[arg(short, long, default_value_t = 10.0, help = "Duration in seconds")]
+    duration: f64,
+    #[arg(
+        short,
+        long,
+        default_value = "25",
+        help = "Frame rate, e.g. 25, 29.97df, 23.976"
+    )]
+    fps: FrameRate,
+    #[arg(short = 'r', long, default_value_t = 48_000)]
+    sample_rate: u32,
+    #[arg(
+        short,
+        long,
+        default_value_t = 16,
+        help = "Bits per sample: 8, 16, 24 or 32 (float)"
+    )]
+    bits: u16,
+    #[arg(short, long, default_value_t = -3.0, allow_hyphen_values = true, help = "Level in dBFS")]
+    level: f64,
+    #[arg(long, default_value_t = 40.0, help = "Rise time in microseconds")]
+    rise_time: f64,
+    #[arg(short, long, help = "User bits as hex, e.g. 0x12345678")]
+    user_bits: Option<String>,
+    #[arg(long, help = "Date to put in the user bits, YYYY-MM-DD")]
+    date: Option<String>,
+    #[arg(
+        long,
+        default_value = "+0000",
+        help = "Timezone of the date, e.g. +0100"
+    )]
+    timezone: String,
+    #[arg(long, help = "Flag the timecode as wall clock time")]
+    clock: bool,
+    #[arg(long, default_value_t = 0.0, help = "Seconds of LTC before the start")]
+    pre_roll: f64,
+    #[arg(long, default_value_t = 0.0, help = "Seconds of LTC after the end")]
+    post_roll: f64,
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        help = "Seconds to fade the LTC out over at the end"
+    )]
+    fade_out: f64,
+    #[arg(
+        long,
+        help = "Output format, by default WAV for .wav files and raw otherwise"
+    )]
+    format: Option<Format>,
+}
+
+// Raw PCM is little endian: 8 bit unsigned, 16 and 24 bit signed or 32 bit float
+fn raw_bytes(samples: &[SampleType], bits: u16, out: &mut Vec<u8>) {
+    out.clear();
+    for &sample in samples {
+        match bits {
+            8 => out.push(sample),
+            16 => out.extend_from_slice(&i16::from_ltc(sample).to_le_bytes()),
+            24 => out.extend_from_slice(&((i16::from_ltc(sample) as i32) << 8).to_le_bytes()[..3]),
+            _ => out.extend_from_slice(&f32::from_ltc(sample).to_le_bytes()),
+        }
+    }
+}
+
+fn write_raw(track: &mut LTCTrack, out: &mut impl Write, bits: u16) -> Result<(), Box<dyn Error>> {
+    let mut block = [0; 4096];
+    let mut bytes = Vec::with_capacity(block.len() * 4);
+    loop {
+        let n = track.fill(&mut block)?;
+        raw_bytes(&block[..n], bits, &mut bytes);
+        out.write_all(&bytes)?;
+        if n < block.len() {
+            break;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_wav<W: Write + Seek>(
+    track: &mut LTCTrack,
+    writer: &mut hound::WavWriter<W>,
+    bits: u16,
+) -> Result<(), Box<dyn Error>> {
+    let mut block = [0; 4096];
+    loop {
+        let n = track.fill(&mut block)?;
+        for &sample in &block[..n] {
+            match bits {
+                8 => writer.write_sample((sample as i16 - 128) as i8)?,
+                16 => writer.write_sample(i16::from_ltc(sample))?,
+                24 => writer.write_sample((i16::from_ltc(sample) as i32) << 8)?,
+                _ => writer.write_sample(f32::from_ltc(sample))?,
+            }
+        }
+        if n < block.len() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn parse_date(date: &str) -> Option<(u8, u8, u8)> {
+    let mut fields = date.split('-').map(|field| field.parse::<u32>().ok());
+    let (year, month, day) = (fields.next()??, fields.next()??, fields.next()??);
+    if fields.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(((year % 100) as u8, month as u8, day as u8))
+}
+
+// Checks the arguments and sets up the track they describe
+fn track(args: &Args) -> Result<LTCTrack, Box<dyn Error>> {
+    if ![8, 16, 24, 32].contains(&args.bits) {
+        return Err("bits must be 8, 16, 24 or 32".into());
+    }
+    if args.date.is_some() && args.user_bits.is_some() {
+        return Err(
+            "the date is carried in the user bits, use either --date or --user-bits".into(),
+        );
+    }
+
+    let mut start: SMPTETimecode = args.start.parse()?;
+    if start.frame() as u32 >= args.fps.timebase() {
+        return Err(format!("frame {} does not exist at {} fps", start.frame(), args.fps).into());
+    }
+    if args
+        .fps
+        .is_dropped_label(start.minutes(), start.seconds(), start.frame())
+    {
+        return Err(format!("{} is dropped at {} fps", args.start, args.fps).into());
+    }
+    let mut flags = LtcBgFlags::default();
+    if let Some(date) = &args.date {
+        let (years, months, days) = parse_date(date).ok_or("invalid date, expected YYYY-MM-DD")?;
+        let timezone: Timezone = args.timezone.parse()?;
+        start = SMPTETimecode::new(
+            timezone,
+            years,
+            months,
+            days,
+            start.hours(),
+            start.minutes(),
+            start.seconds(),
+            start.frame(),
+        );
+        flags.set(LtcBgFlagsKind::LTC_USE_DATE);
+    }
+    if args.clock {
+        flags.set(LtcBgFlagsKind::LTC_TC_CLOCK);
+    }
+
+    let config = LTCTrackConfig {
+        generator: LTCGeneratorConfig {
+            sample_rate: args.sample_rate as f64,
+            rate: args.fps,
+            flags,
+            volume: args.level,
+            filter: args.rise_time,
+        },
+        pre_roll: args.pre_roll,
+        post_roll: args.post_roll,
+        fade_out: args.fade_out,
+        ..Default::default()
+    };
+    let mut track = LTCTrack::try_new(&config, &start, args.duration)?;
+    if let Some(user_bits) = &args.user_bits {
+        let hex = user_bits.trim_start_matches("0x").trim_start_matches("0X");
+        track.set_user_bits(u32::from_str_radix(hex, 16)?);
+    }
+    Ok(track)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut track = track(&args)?;
+
+    let stdout = args.output.as_os_str() == "-";
+    let format = args.format.unwrap_or_else(|| {
+        match args
+            .output
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some(extension) if !stdout && extension.eq_ignore_ascii_case("wav") => Format::Wav,
+            _ => Format::Raw,
+        }
+    });
+    match format {
+        Format::Wav if stdout => return Err("WAV can't be written to stdout".into()),
+        Format::Wav => {
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: args.sample_rate,
+                bits_per_sample: args.bits,
+                sample_format: if args.bits == 32 {
+                    hound::SampleFormat::Float
+                } else {
+                    hound::SampleFormat::Int
+                },
+            };
+            let mut writer = hound::WavWriter::create(&args.output, spec)?;
+            write_wav(&mut track, &mut writer, args.bits)?;
+            writer.finalize()?;
+        }
+        Format::Raw if stdout => write_raw(
+            &mut track,
+            &mut BufWriter::new(io::stdout().lock()),
+            args.bits,
+        )?,
+        Format::Raw => {
+            let mut file = BufWriter::new(File::create(&args.output)?);
+            write_raw(&mut track, &mut file, args.bits)?
+        }
+    }
+
+    if !stdout {
+        eprintln!(
+            "Wrote {} samples ({:.2} s) to '{}'",
+            track.position(),
+            track.position() as f64 / args.sample_rate as f64,
+            args.output.display()
+        );
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &[&str]) -> Args {
+        Args::try_parse_from(["ltc-gen", "-"].iter().chain(options)).unwrap()
+    }
+
+    // Raw 8 bit output of the track and the frames decoded from it with their first sample
+    fn generate(options: &[&str], rate: FrameRate) -> (Vec<u8>, Vec<(String, i64)>) {
+        let mut out = Vec::new();
+        write_raw(&mut track(&args(options)).unwrap(), &mut out, 8).unwrap();
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&out, 0);
+        let mut labels = Vec::new();
+        while let Some(frame) = decoder.read() {
+            if rate.is_high_frame_rate() {
+                for frame in hfr_frames(&frame, rate, LtcBgFlags::default()).unwrap() {
+                    labels.push((frame.timecode.to_time_string(false), frame.start));
+                }
+            } else {
+                let timecode = frame.ltc().to_timecode(LtcBgFlags::default());
+                labels.push((timecode.to_time_string(false), frame.off_start()));
+            }
+        }
+        (out, labels)
+    }
+
+    #[test]
+    fn test_track() {
+        let (out, labels) = generate(
+            &[
+                "--start",
+                "01:00:00:00",
+                "--duration",
+                "1",
+                "--pre-roll",
+                "0.2",
+                "--post-roll",
+                "0.2",
+                "--bits",
+                "8",
+            ],
+            FrameRate::FPS_25,
+        );
+
+        // The same as the track on its own
+        let config = LTCTrackConfig {
+            pre_roll: 0.2,
+            post_roll: 0.2,
+            ..Default::default()
+        };
+        let mut track = LTCTrack::try_new(&config, &"01:00:00:00".parse().unwrap(), 1.0).unwrap();
+        let mut expected = vec![0u8; out.len() + 1];
+        assert_eq!(track.fill(&mut expected).unwrap(), out.len());
+        assert_eq!(out, expected[..out.len()]);
+
+        // 5 frames of pre-roll, 25 of program and 5 of post-roll, then the edge ending the last
+        assert_eq!(out.len(), 35 * 1920 + 1);
+        let start = labels
+            .iter()
+            .find(|(label, _)| label == "01:00:00:00")
+            .unwrap();
+        assert!((start.1 - 5 * 1920).abs() <= 1, "{}", start.1);
+        let (last, end) = labels.last().unwrap();
+        assert_eq!(last, "01:00:01:04");
+        assert!((end - 34 * 1920).abs() <= 1, "{end}");
+    }
+
+    #[test]
+    fn test_high_frame_rate() {
+        let (_, labels) = generate(
+            &["--start", "00:00:00:45", "--duration", "0.2", "--fps", "50"],
+            FrameRate::FPS_50,
+        );
+        assert_eq!(labels.first().unwrap().0, "00:00:00:45");
+        assert_eq!(labels.last().unwrap().0, "00:00:01:04");
+    }
+
+    #[test]
+    fn test_invalid() {
+        for options in [
+            &["--bits", "12"][..],
+            &["--fps", "25", "--start", "00:00:00:25"],
+            &["--pre-roll=-1"],
+            &["--date", "2024-13-01"],
+            &["--fps", "29.97df", "--start", "00:01:00:00"],
+            &["--fps", "29.97df", "--start", "00:01:00;01"],
+        ] {
+            assert!(track(&args(options)).is_err(), "{options:?}");
+        }
+        for options in [
+            &["--fps", "29.97df", "--start", "00:01:00;02"][..],
+            &["--fps", "29.97df", "--start", "00:10:00;00"],
+            &["--fps", "29.97", "--start", "00:01:00:00"],
+        ] {
+            assert!(track(&args(options)).is_ok(), "{options:?}");
+        }
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        let mut bytes = Vec::new();
+        for (bits, expected) in [
+            (8, vec![0, 128, 255]),
+            (16, vec![0x00, 0x80, 0x00, 0x00, 0x00, 0x7F]),
+            (
+                24,
+                vec![0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7F],
+            ),
+        ] {
+            raw_bytes(&[0, 128, 255], bits, &mut bytes);
+            assert_eq!(bytes, expected, "{bits}");
+        }
+        raw_bytes(&[0, 128], 32, &mut bytes);
+        assert_eq!(&bytes[..4], &(-128.0f32 / 127.0).to_le_bytes());
+        assert_eq!(&bytes[4..], &0.0f32.to_le_bytes());
+    }
+}
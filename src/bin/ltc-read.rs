@@ -0,0 +1,339 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::process::exit;
+
+use clap::{Parser, ValueEnum};
+use libltc_rs::prelude::*;
+
+use common::{AudioInput, InputArgs};
+
+mod common;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "ltc-read", version, about = "Decode LTC from WAV or raw PCM")]
+struct Args {
+    #[command(flatten)]
+    input: InputArgs,
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        help = "Channel to decode, starting at 1"
+    )]
+    channel: u16,
+    #[arg(
+        short,
+        long,
+        default_value = "25",
+        help = "Expected frame rate, e.g. 25, 29.97df"
+    )]
+    fps: FrameRate,
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    #[arg(long, help = "Read a date from the user bits")]
+    date: bool,
+}
+
+#[derive(Clone)]
+struct Record {
+    timecode: String,
+    date: Option<String>,
+    user_bits: u32,
+    start: i64,
+    end: i64,
+    reverse: bool,
+    level: f64,
+}
+
+impl Record {
+    fn new(frame: &LTCFrameExt, date: bool) -> Self {
+        let flags = if date {
+            *LtcBgFlags::default().set(LtcBgFlagsKind::LTC_USE_DATE)
+        } else {
+            LtcBgFlags::default()
+        };
+        let ltc = frame.ltc();
+        let timecode = ltc.to_timecode(flags);
+        Record {
+            timecode: timecode.to_time_string(ltc.dfbit() == 1),
+            date: date.then(|| {
+                // Two digit years, the same window libltc's examples use
+                let century = if timecode.years() < 67 { 2000 } else { 1900 };
+                format!(
+                    "{:04}-{:02}-{:02} {}",
+                    century + timecode.years() as u32,
+                    timecode.months(),
+                    timecode.days(),
+                    timecode.timezone().to_string().trim_end_matches('\0')
+                )
+            }),
+            user_bits: ltc.get_user_bits(),
+            start: frame.off_start(),
+            end: frame.off_end(),
+            reverse: frame.reverse(),
+            level: frame.volume(),
+        }
+    }
+
+    // One record per video frame, above 30 fps every LTC frame spans two
+    fn frames(
+        frame: &LTCFrameExt,
+        rate: FrameRate,
+        date: bool,
+    ) -> Result<Vec<Self>, TimecodeError> {
+        let record = Record::new(frame, date);
+        if !rate.is_high_frame_rate() {
+            return Ok(vec![record]);
+        }
+        let drop_frame = frame.ltc().dfbit() == 1;
+        Ok(hfr_frames(frame, rate, LtcBgFlags::default())?
+            .iter()
+            .map(|hfr| Record {
+                timecode: hfr.timecode.to_time_string(drop_frame),
+                start: hfr.start,
+                end: hfr.end,
+                ..record.clone()
+            })
+            .collect())
+    }
+
+    fn direction(&self) -> &'static str {
+        if self.reverse {
+            "reverse"
+        } else {
+            "forward"
+        }
+    }
+
+    fn write(&self, out: &mut impl Write, format: OutputFormat) -> io::Result<()> {
+        let date = self.date.as_deref().unwrap_or_default();
+        match format {
+            OutputFormat::Text => writeln!(
+                out,
+                "{}{}{} {:08x} {:>10} {:>10} {:7} {:6.1} dBFS",
+                date,
+                if date.is_empty() { "" } else { " " },
+                self.timecode,
+                self.user_bits,
+                self.start,
+                self.end,
+                self.direction(),
+                self.level
+            ),
+            OutputFormat::Csv => writeln!(
+                out,
+                "{},{},{:08x},{},{},{},{:.1}",
+                self.timecode,
+                date,
+                self.user_bits,
+                self.start,
+                self.end,
+                self.direction(),
+                self.level
+            ),
+            OutputFormat::Jsonl => {
+                write!(out, "{{\"timecode\":\"{}\"", self.timecode)?;
+                if let Some(date) = &self.date {
+                    write!(out, ",\"date\":\"{}\"", date)?;
+                }
+                writeln!(
+                    out,
+                    ",\"user_bits\":\"{:08x}\",\"start\":{},\"end\":{},\"direction\":\"{}\",\"level\":{:.1}}}",
+                    self.user_bits,
+                    self.start,
+                    self.end,
+                    self.direction(),
+                    self.level
+                )
+            }
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mut input = AudioInput::open(&args.input)?;
+    let channels = input.channels() as usize;
+    if args.channel == 0 || args.channel as usize > channels {
+        return Err(format!(
+            "channel {} does not exist, there are {}",
+            args.channel, channels
+        )
+        .into());
+    }
+    let channel = args.channel as usize - 1;
+
+    // Above 30 fps the LTC runs at the frame pair rate
+    let ltc_rate = if args.fps.is_high_frame_rate() {
+        args.fps.pair_rate()
+    } else {
+        args.fps
+    };
+    let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig {
+        apv: ltc_rate
+            .samples_per_frame(input.sample_rate() as f64)
+            .round() as i32,
+        ..Default::default()
+    })?;
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    if args.output == OutputFormat::Csv {
+        writeln!(out, "timecode,date,user_bits,start,end,direction,level")?;
+    }
+
+    let mut interleaved = vec![0.0; 1024 * channels];
+    let mut samples = vec![0.0; 1024];
+    let mut position = 0;
+    loop {
+        let n = input.read(&mut interleaved)?;
+        if n == 0 {
+            break;
+        }
+        for (sample, frame) in samples.iter_mut().zip(interleaved.chunks_exact(channels)) {
+            *sample = frame[channel];
+        }
+        decoder.write_float(&samples[..n], position);
+        position += n as i64;
+
+        while let Some(frame) = decoder.read() {
+            for record in Record::frames(&frame, args.fps, args.date)? {
+                record.write(&mut out, args.output)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The records of a few frames from 10:00:00:00 on 2024-05-06 UTC, dated in the user bits
+    fn records(date: bool) -> Vec<Record> {
+        let mut flags = LtcBgFlags::default();
+        flags.set(LtcBgFlagsKind::LTC_USE_DATE);
+        let mut encoder = LTCEncoder::try_new(&LTCEncoderConfig {
+            flags,
+            ..Default::default()
+        })
+        .unwrap();
+        encoder.set_timecode(&SMPTETimecode::new(
+            Timezone::default(),
+            24,
+            5,
+            6,
+            10,
+            0,
+            0,
+            0,
+        ));
+        let mut audio = Vec::new();
+        for _ in 0..3 {
+            encoder.encode_frame();
+            let (buf, len) = encoder.get_buf_ref(true);
+            audio.extend_from_slice(&buf[..len]);
+            encoder.inc_timecode().unwrap();
+        }
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig::default()).unwrap();
+        decoder.write(&audio, 0);
+        let mut records = Vec::new();
+        while let Some(frame) = decoder.read() {
+            records.push(Record::new(&frame, date));
+        }
+        records
+    }
+
+    fn output(record: &Record, format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        record.write(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_records() {
+        let records = records(true);
+        let record = records.first().unwrap();
+        assert_eq!(record.timecode, "10:00:00:00");
+        assert_eq!(record.date.as_deref(), Some("2024-05-06 +0000"));
+        assert!(!record.reverse);
+        assert!(record.start <= 1, "{}", record.start);
+        assert_eq!(records[1].timecode, "10:00:00:01");
+
+        let csv = output(record, OutputFormat::Csv);
+        assert!(csv.starts_with("10:00:00:00,2024-05-06 +0000,"), "{csv}");
+        assert!(csv.contains(",forward,"), "{csv}");
+        let json = output(record, OutputFormat::Jsonl);
+        assert!(
+            json.starts_with("{\"timecode\":\"10:00:00:00\",\"date\":\"2024-05-06 +0000\""),
+            "{json}"
+        );
+        assert!(json.ends_with("}\n"), "{json}");
+        let text = output(record, OutputFormat::Text);
+        assert!(text.starts_with("2024-05-06 +0000 10:00:00:00 "), "{text}");
+    }
+
+    #[test]
+    fn test_records_without_date() {
+        // Without --date the user bits are reported as they are
+        let records = records(false);
+        let record = records.first().unwrap();
+        assert_eq!(record.date, None);
+        assert_ne!(record.user_bits, 0);
+        let text = output(record, OutputFormat::Text);
+        assert!(
+            text.starts_with(&format!("10:00:00:00 {:08x} ", record.user_bits)),
+            "{text}"
+        );
+        assert!(!output(record, OutputFormat::Jsonl).contains("date"));
+    }
+
+    #[test]
+    fn test_high_frame_rate() {
+        let config = LTCTrackConfig {
+            generator: LTCGeneratorConfig {
+                rate: FrameRate::FPS_50,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let start: SMPTETimecode = "10:00:00:48".parse().unwrap();
+        let mut track = LTCTrack::try_new(&config, &start, 0.2).unwrap();
+        let mut audio = vec![0u8; 10_000];
+        let n = track.fill(&mut audio).unwrap();
+        let mut decoder = LTCDecoder::try_new(&LTCDecoderConfig {
+            apv: 1920,
+            ..Default::default()
+        })
+        .unwrap();
+        decoder.write(&audio[..n], 0);
+
+        let mut records = Vec::new();
+        while let Some(frame) = decoder.read() {
+            records.extend(Record::frames(&frame, FrameRate::FPS_50, false).unwrap());
+        }
+        let labels: Vec<_> = records.iter().map(|record| &record.timecode[..]).collect();
+        assert_eq!(
+            labels[..4],
+            ["10:00:00:48", "10:00:00:49", "10:00:01:00", "10:00:01:01"]
+        );
+        // Each frame is half an LTC frame long
+        for record in &records {
+            assert!((959..=961).contains(&(record.end - record.start + 1)));
+        }
+    }
+}
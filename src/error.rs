@@ -8,6 +8,8 @@ pub enum LTCEncoderError {
     BufferSizeError,
     VolumeError,
     EncodeError,
+    ConfigError,
+    HighFrameRate,
     TimecodeError(TimecodeError),
 }
 
@@ -20,11 +22,57 @@ pub enum LTCDecoderError {
 #[derive(Debug)]
 pub enum TimecodeError {
     InvalidReturn,
+    ParseTimecode,
+    ParseFrameRate,
+    ParseTimezone,
+    NotHighFrameRate,
+    ParseFeetFrames,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MTCError {
+    UnsupportedRate,
+    InvalidMessage,
+}
+
+#[cfg(feature = "artnet")]
+#[derive(Debug)]
+pub enum ArtNetError {
+    Io(std::io::Error),
+    UnsupportedRate,
+    InvalidPacket,
+}
+
+#[cfg(feature = "osc")]
+#[derive(Debug)]
+pub enum OSCError {
+    Io(std::io::Error),
+    InvalidMessage,
 }
 
 impl Error for LTCEncoderError {}
 impl Error for LTCDecoderError {}
 impl Error for TimecodeError {}
+impl Error for MTCError {}
+#[cfg(feature = "artnet")]
+impl Error for ArtNetError {}
+
+#[cfg(feature = "osc")]
+impl Error for OSCError {}
+
+#[cfg(feature = "artnet")]
+impl From<std::io::Error> for ArtNetError {
+    fn from(e: std::io::Error) -> Self {
+        ArtNetError::Io(e)
+    }
+}
+
+#[cfg(feature = "osc")]
+impl From<std::io::Error> for OSCError {
+    fn from(e: std::io::Error) -> Self {
+        OSCError::Io(e)
+    }
+}
 
 impl From<TimecodeError> for LTCEncoderError {
     fn from(e: TimecodeError) -> Self {
@@ -46,6 +94,13 @@ impl std::fmt::Display for LTCEncoderError {
             LTCEncoderError::BufferSizeError => write!(f, "Error setting buffer size"),
             LTCEncoderError::VolumeError => write!(f, "Error setting volume"),
             LTCEncoderError::EncodeError => write!(f, "Error during encoding"),
+            LTCEncoderError::ConfigError => write!(f, "Invalid generator configuration"),
+            LTCEncoderError::HighFrameRate => {
+                write!(
+                    f,
+                    "LTC runs at 30 fps at most, use hfr_generator above that"
+                )
+            }
             LTCEncoderError::TimecodeError(e) => write!(f, "Timecode error: {}", e),
         }
     }
@@ -64,6 +119,52 @@ impl std::fmt::Display for TimecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             TimecodeError::InvalidReturn => write!(f, "Invalid return value from C function"),
+            TimecodeError::ParseTimecode => write!(f, "Invalid timecode, expected HH:MM:SS:FF"),
+            TimecodeError::ParseFrameRate => write!(f, "Invalid frame rate"),
+            TimecodeError::ParseTimezone => write!(f, "Invalid timezone, expected +HHMM"),
+            TimecodeError::NotHighFrameRate => {
+                write!(f, "Frame pairs need a frame rate above 30 fps")
+            }
+            TimecodeError::ParseFeetFrames => {
+                write!(f, "Invalid feet and frames, expected FEET+FF")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for MTCError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MTCError::UnsupportedRate => {
+                write!(f, "MTC only carries 24, 25, 29.97 drop frame and 30 fps")
+            }
+            MTCError::InvalidMessage => write!(f, "Invalid MTC message"),
+        }
+    }
+}
+
+#[cfg(feature = "artnet")]
+impl std::fmt::Display for ArtNetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArtNetError::Io(e) => write!(f, "Art-Net socket error: {}", e),
+            ArtNetError::UnsupportedRate => {
+                write!(
+                    f,
+                    "Art-Net only carries 24, 25, 29.97 drop frame and 30 fps"
+                )
+            }
+            ArtNetError::InvalidPacket => write!(f, "Invalid Art-Net timecode packet"),
+        }
+    }
+}
+
+#[cfg(feature = "osc")]
+impl std::fmt::Display for OSCError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OSCError::Io(e) => write!(f, "OSC socket error: {}", e),
+            OSCError::InvalidMessage => write!(f, "Invalid OSC message"),
         }
     }
 }
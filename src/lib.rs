@@ -5,10 +5,29 @@ mod error;
 mod raw;
 
 pub mod prelude {
+    #[cfg(feature = "artnet")]
+    pub use super::api::artnet::*;
+    pub use super::api::chase::*;
     pub use super::api::consts::*;
+    pub use super::api::continuity::*;
+    pub use super::api::convert::*;
     pub use super::api::decoder::*;
     pub use super::api::encoder::*;
+    pub use super::api::film::*;
     pub use super::api::frame::*;
+    pub use super::api::generator::*;
+    pub use super::api::hfr::*;
+    pub use super::api::jam::*;
+    pub use super::api::mtc::*;
+    #[cfg(feature = "osc")]
+    pub use super::api::osc::*;
+    pub use super::api::range::*;
+    pub use super::api::rate::*;
+    pub use super::api::sample::*;
+    pub use super::api::shuttle::*;
+    pub use super::api::speed::*;
+    pub use super::api::stamp::*;
+    pub use super::api::track::*;
     pub use super::api::*;
 }
 